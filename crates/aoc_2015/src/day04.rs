@@ -1,28 +1,20 @@
-const INPUT: &'static str = "bgvyzdsv";
-
-fn run(mask: u8) -> i32 {
-    let mut i = 0;
-    loop {
-        let s = format!("{}{}", INPUT, i);
-        let digest = md5::compute(s);
+use bcrypt::md5::batch::search_hex_prefix;
 
-        if digest[0] == 0 && digest[1] == 0 && (digest[2] & mask) == 0 {
-            break;
-        }
+const INPUT: &'static str = "bgvyzdsv";
 
-        i += 1;
-    }
+fn run(prefix: &str) -> u64 {
+    let (i, _) = search_hex_prefix(INPUT, 0..u64::MAX, prefix).next().unwrap();
     i
 }
 
 #[test]
 fn part1() {
-    let answer = run(0xF0);
+    let answer = run("00000");
     assert_eq!(answer, 254575);
 }
 
 #[test]
 fn part2() {
-    let answer = run(0xFF);
+    let answer = run("000000");
     assert_eq!(answer, 1038736);
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use aoc_common::{file_lines, full_permutations, IdMap, IteratorExt};
+use aoc_common::{file_lines, tour::{longest_path, shortest_path}, IdMap, IteratorExt};
 
 fn input() -> Vec<Vec<i32>> {
     let lines = file_lines("inputs/day09.txt").to_vec();
@@ -24,19 +24,13 @@ fn input() -> Vec<Vec<i32>> {
 fn run(min: bool) -> i32 {
     let graph = input();
 
-    let nums = (0..graph.len()).to_vec();
-    let full_permuatations = full_permutations(&nums);
-    let totals = full_permuatations.iter().map(|perm| {
-        perm.windows(2).map(|w| graph[*w[0]][*w[1]]).sum::<i32>()
-    });
-
-    let extreme = if min {
-        totals.min()
+    let tour = if min {
+        shortest_path(&graph)
     } else {
-        totals.max()
+        longest_path(&graph)
     };
 
-    extreme.unwrap()
+    tour.cost
 }
 
 #[test]
@@ -1,4 +1,4 @@
-use aoc_common::{file_lines, full_permutations, IdMap, IteratorExt};
+use aoc_common::{file_lines, tour::longest_tour, IdMap, IteratorExt};
 
 type Graph = Vec<Vec<i32>>;
 
@@ -39,22 +39,13 @@ fn input(add_me: bool) -> Graph {
 
 fn run(add_me: bool) -> i32 {
     let graph: Graph = input(add_me);
-    let ids = (0..graph.len()).to_vec();
 
-    full_permutations(&ids).iter().map(|perm| {
-        let mut total = 0;
-        for i in 0..perm.len() {
-            let left = if i == 0 { perm.len() - 1 } else { i - 1 };
-            let right = if i == perm.len() - 1 { 0 } else { i + 1 };
+    // sitting next to each other is worth both people's happiness, whichever way round
+    let pairs = (0..graph.len()).map(|a| (0..graph.len()).map(|b| {
+        if a == b { 0 } else { graph[a][b] + graph[b][a] }
+    }).to_vec()).to_vec();
 
-            let left = *perm[left];
-            let right = *perm[right];
-            let center = *perm[i];
-
-            total += graph[center][left] + graph[center][right];
-        }
-        total
-    }).max().unwrap()
+    longest_tour(&pairs).cost
 }
 
 #[test]
@@ -1,4 +1,6 @@
-use aoc_common::{file_lines, IteratorExt};
+use std::str::FromStr;
+
+use aoc_common::vm::{Flow, Instruction, Machine};
 
 use Instr::*;
 
@@ -6,93 +8,68 @@ enum Instr {
     Hlf(usize),
     Tpl(usize),
     Inc(usize),
-    Jmp(i32),
-    Jie(usize, i32),
-    Jio(usize, i32),
-}
-
-fn input() -> Vec<Instr> {
-    file_lines("inputs/day23.txt").map(|l| {
-        let split = l.split(' ').to_vec();
-
-        let reg: &str;
-        let offset: i32;
-
-        if split.len() == 3 {
-            reg = split[1].trim_end_matches(',');
-            offset = split[2].trim_start_matches('+').parse().unwrap();
-        } else if split.len() == 2 {
-            let n = split[1].trim_end_matches('+');
-            if let Ok(n) = n.parse::<i32>() {
-                offset = n;
-                reg = "a";
-            } else {
-                reg = split[1];
-                offset = 0;
-            }
-        } else {
-            panic!()
-        }
-
-        let reg: usize = match reg {
-            "a" => 0,
-            "b" => 1,
-            _ => panic!()
-        };
-
-        match split[0] {
-            "hlf" => Hlf(reg),
-            "tpl" => Tpl(reg),
-            "inc" => Inc(reg),
-            "jmp" => Jmp(offset),
-            "jie" => Jie(reg, offset),
-            "jio" => Jio(reg, offset),
-            _ => panic!()
-        }
-    }).collect()
+    Jmp(i64),
+    Jie(usize, i64),
+    Jio(usize, i64),
 }
 
-fn run(code: &[Instr], regs: &mut[i32]) {
-    fn add_offset(ip: usize, offset: i32) -> usize {
-        (ip as i32 + (offset - 1)) as usize
-    }
-
-    let mut ip = 0;
+impl Instruction for Instr {
+    type Word = u64;
+    const REGISTERS: usize = 2;
 
-    loop {
-        if ip >= code.len() {
-            break;
-        }
-
-        match code[ip] {
+    fn execute(&self, regs: &mut [u64]) -> Flow<u64> {
+        match *self {
             Hlf(reg) => regs[reg] /= 2,
             Tpl(reg) => regs[reg] *= 3,
             Inc(reg) => regs[reg] += 1,
-            Jmp(offset) => ip = add_offset(ip, offset),
-            Jie(reg, offset) => if regs[reg] % 2 == 0 { ip = add_offset(ip, offset) },
-            Jio(reg, offset) => if regs[reg] == 1 { ip = add_offset(ip, offset) },
+            Jmp(offset) => return Flow::Jump(offset),
+            Jie(reg, offset) => if regs[reg].is_multiple_of(2) { return Flow::Jump(offset) },
+            Jio(reg, offset) => if regs[reg] == 1 { return Flow::Jump(offset) },
         }
+        Flow::Next
+    }
+}
+
+impl FromStr for Instr {
+    type Err = String;
 
-        ip += 1;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (op, args) = s.split_once(' ').ok_or_else(|| format!("invalid instruction '{}'", s))?;
+        let reg = |r: &str| match r {
+            "a" => Ok(0),
+            "b" => Ok(1),
+            _ => Err(format!("unknown register '{}'", r)),
+        };
+        let offset = |o: &str| o.parse::<i64>().map_err(|_| format!("invalid offset '{}'", o));
+        let jump = || args.split_once(", ").ok_or_else(|| format!("expected 'r, offset', found '{}'", args));
+
+        Ok(match op {
+            "hlf" => Hlf(reg(args)?),
+            "tpl" => Tpl(reg(args)?),
+            "inc" => Inc(reg(args)?),
+            "jmp" => Jmp(offset(args)?),
+            "jie" => { let (r, o) = jump()?; Jie(reg(r)?, offset(o)?) }
+            "jio" => { let (r, o) = jump()?; Jio(reg(r)?, offset(o)?) }
+            _ => return Err(format!("unknown instruction '{}'", op)),
+        })
     }
 }
 
 #[test]
 fn part1() {
-    let code = input();
-    let mut regs = [0, 0];
-    run (&code, &mut regs);
+    let mut machine: Machine<Instr> = Machine::from_file("inputs/day23.txt");
+    machine.run();
     
-    let answer = regs[1];
+    let answer = machine.regs()[1];
     assert_eq!(answer, 307);
 }
 
 #[test]
 fn part2() {
-    let code = input();
-    let mut regs = [1, 0];
-    run (&code, &mut regs);
+    let mut machine: Machine<Instr> = Machine::from_file("inputs/day23.txt");
+    machine.regs_mut()[0] = 1;
+    machine.run();
     
-    let answer = regs[1];
+    let answer = machine.regs()[1];
     assert_eq!(answer, 160);
-}
\ No newline at end of file
+}
@@ -1,20 +1,24 @@
+use std::collections::HashMap;
+
 use aoc_common::{file_lines, IteratorExt};
 
 use Identifier::*;
 use Instruction::*;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Identifier {
     Reg(usize),
     Const(i32),
 }
 
+#[derive(Clone)]
 enum Instruction {
     Cpy(Identifier, Identifier),
     Inc(Identifier),
     Dec(Identifier),
     Jnz(Identifier, Identifier),
     Tgl(Identifier),
+    Out(Identifier),
 }
 
 impl Instruction {
@@ -22,24 +26,106 @@ impl Instruction {
         match *self {
             Inc(a) => Dec(a),
             Dec(a) => Inc(a),
-            Tgl(a) => Inc(a),
+            Tgl(a) | Out(a) => Inc(a),
             Jnz(a, b) => Cpy(a, b),
             Cpy(a, b) => Jnz(a, b),
         }
     }
 }
 
+/// Loops replaced by a single operation, keyed by the address of their first instruction.
+#[derive(Copy, Clone)]
+enum Fused {
+    /// `inc/dec target; inc/dec counter; jnz counter -2` in either order
+    Add { target: usize, delta: i32, counter: usize, step: i32 },
+    /// `cpy src inner; <add loop counting inner down>; inc/dec outer; jnz outer -5`
+    Mul { src: Identifier, target: usize, delta: i32, inner: usize, inner_step: i32, outer: usize, outer_step: i32 },
+}
+
+impl Fused {
+    fn len(&self) -> i32 {
+        match self {
+            Fused::Add { .. } => 3,
+            Fused::Mul { .. } => 6,
+        }
+    }
+
+    fn find(code: &[Instruction], ip: usize) -> Option<Fused> {
+        Self::find_mul(code, ip).or_else(|| Self::find_add(code, ip))
+    }
+
+    fn find_add(code: &[Instruction], ip: usize) -> Option<Fused> {
+        let [a, b, Jnz(Reg(counter), Const(-2))] = code.get(ip..ip + 3)? else {
+            return None;
+        };
+
+        let (x, y) = (inc_dec(a)?, inc_dec(b)?);
+        let ((target, delta), step) = if x.0 == *counter { (y, x.1) } else if y.0 == *counter { (x, y.1) } else { return None };
+        (target != *counter).then_some(Fused::Add { target, delta, counter: *counter, step })
+    }
+
+    fn find_mul(code: &[Instruction], ip: usize) -> Option<Fused> {
+        let Cpy(src, Reg(inner)) = *code.get(ip)? else {
+            return None;
+        };
+        let Some(Fused::Add { target, delta, counter, step: inner_step }) = Self::find_add(code, ip + 1) else {
+            return None;
+        };
+        let [outer_op, Jnz(Reg(outer), Const(-5))] = code.get(ip + 4..ip + 6)? else {
+            return None;
+        };
+        let (outer_reg, outer_step) = inc_dec(outer_op)?;
+
+        let distinct = counter == inner && outer_reg == *outer && *outer != inner && *outer != target;
+        let src_fixed = ![Reg(inner), Reg(target), Reg(*outer)].contains(&src);
+        (distinct && src_fixed).then_some(Fused::Mul { src, target, delta, inner, inner_step, outer: *outer, outer_step })
+    }
+}
+
+/// Register and change for `inc`/`dec` of a register.
+fn inc_dec(instruction: &Instruction) -> Option<(usize, i32)> {
+    match *instruction {
+        Inc(Reg(r)) => Some((r, 1)),
+        Dec(Reg(r)) => Some((r, -1)),
+        _ => None,
+    }
+}
+
+/// Times a loop stepping `val` by `step` runs before reaching 0, or None if it never does.
+fn iterations(val: i32, step: i32) -> Option<i32> {
+    let n = -val * step;
+    (n > 0).then_some(n)
+}
+
+/// An output stream that repeats forever: `prefix` once, then `cycle` over and over.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OutputCycle {
+    pub prefix: Vec<i32>,
+    pub cycle: Vec<i32>,
+}
+
+#[derive(Clone)]
 pub struct Computer {
     code: Vec<Instruction>,
+    fused: Vec<Option<Fused>>,
     regs: Vec<i32>,
     ip: i32,
-    is_halt: bool
+    is_halt: bool,
+    toggles: usize,
 }
 
 impl Computer {
     pub fn from_file(path: &str) -> Self {
-        let code = file_lines(path).map(|l| {
-            let split = l.split(' ').to_vec();
+        Self::from_lines(file_lines(path))
+    }
+
+    pub fn from_string(code: &str) -> Self {
+        Self::from_lines(code.lines().map(str::trim).filter(|l| !l.is_empty()))
+    }
+
+    fn from_lines<S: AsRef<str>>(lines: impl Iterator<Item = S>) -> Self {
+        let code = lines.map(|l| {
+            let split = l.as_ref().split(' ').to_vec();
             let args = split[1..].iter().map(|s| {
                 match *s {
                     "a" | "b" | "c" | "d" => Reg((s.as_bytes()[0] - b'a') as usize),
@@ -53,17 +139,27 @@ impl Computer {
                 "dec" => Dec(args[0]),
                 "jnz" => Jnz(args[0], args[1]),
                 "tgl" => Tgl(args[0]),
+                "out" => Out(args[0]),
                 _ => panic!()
             }
         }).to_vec();
 
-
-        Self {
+        let mut computer = Self {
             code,
+            fused: Vec::new(),
             regs: vec![0; 4],
             ip: 0,
-            is_halt: false
-        }
+            is_halt: false,
+            toggles: 0,
+        };
+        computer.optimize();
+        computer
+    }
+
+    /// Finds add and multiply loops. Only the first instruction of a loop is marked, so jumping
+    /// into the middle of one still runs the plain instructions.
+    fn optimize(&mut self) {
+        self.fused = (0..self.code.len()).map(|ip| Fused::find(&self.code, ip)).collect();
     }
 
     pub fn regs(&self) -> &[i32] {
@@ -80,8 +176,47 @@ impl Computer {
         }
     }
 
-    fn step(&mut self) {
-        if self.is_halt { return; }
+    /// Runs until the next `out`, returning its value, or None once the program halts.
+    pub fn run_until_output(&mut self) -> Option<i32> {
+        while !self.is_halt {
+            if let Some(val) = self.step() {
+                return Some(val);
+            }
+        }
+        None
+    }
+
+    /// Runs until the state after an `out` repeats, which proves the output from then on
+    /// cycles forever. Gives up with None on halt or after `max_outputs` values. States are
+    /// compared by ip and registers, so a `tgl` starts the search over.
+    pub fn output_cycle(&mut self, max_outputs: usize) -> Option<OutputCycle> {
+        let mut seen: HashMap<(usize, i32, Vec<i32>), usize> = HashMap::new();
+        let mut outputs = Vec::new();
+        while outputs.len() < max_outputs {
+            outputs.push(self.run_until_output()?);
+            let state = (self.toggles, self.ip, self.regs.clone());
+            if let Some(start) = seen.insert(state, outputs.len()) {
+                let cycle = outputs.split_off(start);
+                return Some(OutputCycle { prefix: outputs, cycle });
+            }
+        }
+        None
+    }
+
+    fn step(&mut self) -> Option<i32> {
+        if self.is_halt { return None; }
+
+        if let Some(fused) = self.fused[self.ip as usize] {
+            if self.run_fused(fused) {
+                self.ip += fused.len();
+                if self.ip as usize >= self.code.len() {
+                    self.is_halt = true;
+                }
+                return None;
+            }
+        }
+
+        let mut output = None;
 
         match self.code[self.ip as usize] {
             Cpy(a, b) => {
@@ -108,14 +243,44 @@ impl Computer {
                 let target = self.ip + self.read(a);
                 if target >= 0 && (target as usize) < self.code.len() {
                     self.code[target as usize] = self.code[target as usize].toggle();
+                    // any loop containing the target is no longer valid, and new ones may appear
+                    self.optimize();
+                    self.toggles += 1;
                 }
             }
+            Out(a) => {
+                output = Some(self.read(a));
+            }
         }
 
         self.ip += 1;
         if self.ip as usize >= self.code.len() {
             self.is_halt = true;
         }
+        output
+    }
+
+    /// Applies a fused loop in one go, returns false when the loop wouldn't terminate normally
+    /// and has to be stepped through instead.
+    fn run_fused(&mut self, fused: Fused) -> bool {
+        match fused {
+            Fused::Add { target, delta, counter, step } => {
+                let Some(n) = iterations(self.regs[counter], step) else {
+                    return false;
+                };
+                self.regs[target] += delta * n;
+                self.regs[counter] = 0;
+            }
+            Fused::Mul { src, target, delta, inner, inner_step, outer, outer_step } => {
+                let (Some(n), Some(m)) = (iterations(self.read(src), inner_step), iterations(self.regs[outer], outer_step)) else {
+                    return false;
+                };
+                self.regs[target] += delta * n * m;
+                self.regs[inner] = 0;
+                self.regs[outer] = 0;
+            }
+        }
+        true
     }
 
     fn read(&self, id: Identifier) -> i32 {
@@ -124,4 +289,85 @@ impl Computer {
             Reg(i) => self.regs[i],
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Computer, Fused, OutputCycle};
+
+    #[test]
+    fn fused_add() {
+        let mut machine = Computer::from_string("
+            cpy 5 b
+            inc a
+            dec b
+            jnz b -2");
+        assert!(matches!(machine.fused[1], Some(Fused::Add { target: 0, delta: 1, counter: 1, step: -1 })));
+        machine.run();
+        assert_eq!(machine.regs(), &[5, 0, 0, 0]);
+    }
+
+    #[test]
+    fn fused_mul() {
+        let mut machine = Computer::from_string("
+            cpy 4 d
+            cpy 3 b
+            dec a
+            dec b
+            jnz b -2
+            dec d
+            jnz d -5");
+        assert!(matches!(machine.fused[1], Some(Fused::Mul { target: 0, delta: -1, inner: 1, outer: 3, .. })));
+        machine.run();
+        assert_eq!(machine.regs(), &[-12, 0, 0, 0]);
+    }
+
+    #[test]
+    fn unfused_fallback() {
+        // b starts at 0 and only goes down, so the loop never ends and has to be stepped
+        let mut machine = Computer::from_string("
+            inc a
+            dec b
+            jnz b -2");
+        assert!(machine.fused[0].is_some());
+        for _ in 0..6 {
+            machine.step();
+        }
+        assert_eq!(machine.regs(), &[2, -2, 0, 0]);
+        assert_eq!(machine.ip, 0);
+    }
+
+    #[test]
+    fn toggle_inside_fused() {
+        // the tgl turns the loop's inc into a dec, which has to be fused again
+        let mut machine = Computer::from_string("
+            cpy 2 c
+            tgl c
+            cpy 3 b
+            inc a
+            dec b
+            jnz b -2");
+        assert!(matches!(machine.fused[3], Some(Fused::Add { delta: 1, .. })));
+        machine.run();
+        assert!(matches!(machine.fused[3], Some(Fused::Add { delta: -1, .. })));
+        assert_eq!(machine.regs(), &[-3, 0, 2, 0]);
+    }
+
+    #[test]
+    fn output_cycle() {
+        let mut machine = Computer::from_string("
+            out 5
+            cpy 0 a
+            out a
+            inc a
+            out a
+            dec a
+            jnz 1 -4");
+        assert_eq!(machine.clone().run_until_output(), Some(5));
+        assert_eq!(machine.output_cycle(10), Some(OutputCycle { prefix: vec![5, 0], cycle: vec![1, 0] }));
+
+        let mut halting = Computer::from_string("out 1\nout 2");
+        assert_eq!(halting.output_cycle(10), None);
+        assert_eq!(halting.run_until_output(), None);
+    }
+}
@@ -1,3 +1,5 @@
+use bcrypt::md5::batch::{search_hex_prefix, HexPrefixSearch};
+
 const INPUT: &str = "abbhdwsy";
 const LENGTH: usize = 8;
 
@@ -47,16 +49,14 @@ fn part2() {
 }
 
 struct Md5Iter {
-    seed: String,
-    n: i32,
+    search: HexPrefixSearch,
 }
 
 impl Md5Iter {
     fn new(seed: &str) -> Self {
         Self {
-            seed: seed.to_string(),
-            n: 0
-        }   
+            search: search_hex_prefix(seed, 0..u64::MAX, "00000"),
+        }
     }
 }
 
@@ -64,13 +64,6 @@ impl Iterator for Md5Iter {
     type Item = (u8, u8);
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            self.n += 1;
-            let s = format!("{}{}", self.seed, self.n - 1);
-            let hash = md5::compute(s);
-            if hash[0] == 0 && hash[1] == 0 && hash[2] & 0xF0 == 0 {
-                return Some((hash[2] & 0x0F, hash[3] >> 4));
-            }
-        }
+        self.search.next().map(|(_, hash)| (hash[2] & 0x0F, hash[3] >> 4))
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use aoc_common::{file_lines, map_points_to_string, IteratorExt, Vec2us};
+use aoc_common::{file_lines, ocr, IteratorExt, Vec2us};
 
 fn input() -> Vec<String> {
     file_lines("inputs/day08.txt").collect()
@@ -79,15 +79,6 @@ fn part1() {
 #[test]
 fn part2() {
     let points = run();
-    let answer = map_points_to_string(points.into_iter());
-
-const KNOWN: &str = "
-████  ██   ██  ███   ██  ███  █  █ █   █ ██   ██ 
-█    █  █ █  █ █  █ █  █ █  █ █  █ █   ██  █ █  █
-███  █  █ █  █ █  █ █    █  █ ████  █ █ █  █ █  █
-█    █  █ ████ ███  █ ██ ███  █  █   █  ████ █  █
-█    █  █ █  █ █ █  █  █ █    █  █   █  █  █ █  █
-████  ██  █  █ █  █  ███ █    █  █   █  █  █  ██ ";
-
-    assert_eq!(answer, KNOWN);
+    let answer = ocr(points.into_iter()).unwrap();
+    assert_eq!(answer, "EOARGPHYAO");
 }
\ No newline at end of file
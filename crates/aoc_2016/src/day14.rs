@@ -1,10 +1,11 @@
 use std::collections::VecDeque;
-use bcrypt::md5::Md5;
+use bcrypt::md5::batch::Md5Batch;
 
 const INPUT: &str = "ngcjuoqr";
+const BATCH: usize = 1024;
 
 struct HashIter {
-    hasher: Md5,
+    batch: Md5Batch,
     suffix: usize,
     cached: VecDeque<String>,
     extra_hashes: usize,
@@ -13,7 +14,7 @@ struct HashIter {
 impl HashIter {
     fn new(extra_hashes: usize) -> Self {
         Self {
-            hasher: Md5::new(),
+            batch: Md5Batch::new(),
             suffix: 0,
             cached: VecDeque::new(),
             extra_hashes,
@@ -25,12 +26,9 @@ impl HashIter {
     }
 
     fn next(&mut self) -> String {
+        self.fill(1);
         self.suffix += 1;
-        if let Some(cached) = self.cached.pop_front() {
-            cached
-        } else {
-            self.calc_suffix(self.suffix - 1)
-        }
+        self.cached.pop_front().unwrap()
     }
 
     fn peek(&mut self, offset: usize) -> &String {
@@ -38,23 +36,19 @@ impl HashIter {
             panic!();
         }
 
-        if offset <= self.cached.len() {
-            &self.cached[offset - 1]
-        } else {
-            let suffix = self.suffix + offset - 1;
-            let hash = self.calc_suffix(suffix);
-            self.cached.push_back(hash);
-            &self.cached.back().unwrap()
-        }
+        self.fill(offset);
+        &self.cached[offset - 1]
     }
 
-    fn calc_suffix(&mut self, suffix: usize) -> String {
-        let mut s = format!("{}{}", INPUT, suffix);
-        for _ in 0 .. self.extra_hashes + 1 {
-            let digest = self.hasher.compute(s);
-            s = Self::digest_to_string(&digest);
+    fn fill(&mut self, count: usize) {
+        while self.cached.len() < count {
+            let start = self.suffix + self.cached.len();
+            let messages: Vec<String> = (start..start + BATCH).map(|i| format!("{}{}", INPUT, i)).collect();
+
+            let mut digests = self.batch.compute(&messages);
+            self.batch.stretch(&mut digests, self.extra_hashes);
+            self.cached.extend(digests.iter().map(|d| Self::digest_to_string(d)));
         }
-        s
     }
 
     fn digest_to_string(bytes: &[u8]) -> String {
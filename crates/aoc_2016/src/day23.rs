@@ -14,7 +14,6 @@ fn part1() {
 }
 
 #[test]
-#[ignore] //slow
 fn part2() {
     let answer = run(12);
     assert_eq!(answer, 479006925);
@@ -0,0 +1,313 @@
+use aoc_common::{file_lines, vm::{self, Flow}, IteratorExt};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Value {
+    Reg(usize),
+    Lit(i64),
+}
+
+impl Value {
+    fn from_str(s: &str) -> Self {
+        match s.parse::<i64>() {
+            Ok(val) => Value::Lit(val),
+            Err(_) => Value::Reg((s.as_bytes()[0] - b'a') as usize),
+        }
+    }
+
+    fn resolve(&self, regs: &[i64]) -> i64 {
+        match self {
+            Value::Reg(idx) => regs[*idx],
+            Value::Lit(val) => *val,
+        }
+    }
+
+    fn reg(&self) -> usize {
+        match self {
+            Value::Reg(idx) => *idx,
+            Value::Lit(val) => panic!("expected a register, found {}", val),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Snd,
+    Rcv,
+    Set,
+    Add,
+    Sub,
+    Mul,
+    Mod,
+    Jgz,
+    Jnz,
+}
+
+impl Op {
+    fn from_name(name: &str) -> Op {
+        match name {
+            "snd" => Op::Snd,
+            "rcv" => Op::Rcv,
+            "set" => Op::Set,
+            "add" => Op::Add,
+            "sub" => Op::Sub,
+            "mul" => Op::Mul,
+            "mod" => Op::Mod,
+            "jgz" => Op::Jgz,
+            "jnz" => Op::Jnz,
+            _ => panic!("unknown instruction {}", name),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Instruction {
+    op: Op,
+    x: Value,
+    y: Value,
+    dialect: Dialect,
+}
+
+/// Register holding the last sound played, after the 26 named ones.
+const SOUND: usize = 26;
+
+impl vm::Instruction for Instruction {
+    type Word = i64;
+    const REGISTERS: usize = 27;
+
+    fn execute(&self, regs: &mut [i64]) -> Flow<i64> {
+        let Instruction { op, x, y, dialect } = *self;
+        let (x_val, y_val) = (x.resolve(regs), y.resolve(regs));
+        match op {
+            Op::Snd => match dialect {
+                Dialect::Sound => regs[SOUND] = x_val,
+                Dialect::Message => return Flow::Output(x_val),
+            },
+            Op::Rcv => match dialect {
+                Dialect::Sound => if x_val != 0 { return Flow::Output(regs[SOUND]) },
+                Dialect::Message => return Flow::Receive(x.reg()),
+            },
+            Op::Set => regs[x.reg()] = y_val,
+            Op::Add => regs[x.reg()] += y_val,
+            Op::Sub => regs[x.reg()] -= y_val,
+            Op::Mul => regs[x.reg()] *= y_val,
+            Op::Mod => regs[x.reg()] %= y_val,
+            Op::Jgz => if x_val > 0 { return Flow::Jump(y_val) },
+            Op::Jnz => if x_val != 0 { return Flow::Jump(y_val) },
+        }
+        Flow::Next
+    }
+}
+
+/// What `snd` and `rcv` mean. The tablet first reads them as playing and recovering sounds,
+/// then as sending and receiving values between two copies of the program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    Sound,
+    Message,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Ok,
+    Halt,
+    /// `rcv` with a non-zero value, carrying the last sound played.
+    Recover(i64),
+    Send(i64),
+    /// `rcv` on an empty queue, the instruction will be retried.
+    Blocked,
+}
+
+/// A duet program on a `vm::Machine`. `snd` and `rcv` come out of it as outputs and input.
+#[derive(Clone)]
+pub struct Machine {
+    vm: vm::Machine<Instruction>,
+    dialect: Dialect,
+    sent: usize,
+}
+
+impl Machine {
+    pub fn from_file(path: &str, dialect: Dialect) -> Self {
+        Self::from_lines(file_lines(path), dialect)
+    }
+
+    pub fn from_string(code: &str, dialect: Dialect) -> Self {
+        Self::from_lines(code.lines().map(str::trim).filter(|l| !l.is_empty()), dialect)
+    }
+
+    fn from_lines<S: AsRef<str>>(lines: impl Iterator<Item = S>, dialect: Dialect) -> Self {
+        let code = lines.map(|l| {
+            let split = l.as_ref().split(' ').to_vec();
+            Instruction {
+                op: Op::from_name(split[0]),
+                x: Value::from_str(split[1]),
+                y: split.get(2).map_or(Value::Lit(0), |s| Value::from_str(s)),
+                dialect,
+            }
+        }).to_vec();
+
+        Self { vm: vm::Machine::new(code), dialect, sent: 0 }
+    }
+
+    /// The same program and registers read in another dialect, from the start.
+    fn with_dialect(&self, dialect: Dialect) -> Self {
+        let code = self.vm.program().iter().map(|i| Instruction { dialect, ..*i }).to_vec();
+        let mut vm = vm::Machine::new(code);
+        vm.regs_mut().copy_from_slice(self.vm.regs());
+        Self { vm, dialect, sent: 0 }
+    }
+
+    pub fn reg(&self, name: char) -> i64 {
+        self.vm.regs()[(name as u8 - b'a') as usize]
+    }
+
+    pub fn set_reg(&mut self, name: char, val: i64) {
+        self.vm.regs_mut()[(name as u8 - b'a') as usize] = val;
+    }
+
+    pub fn ip(&self) -> usize {
+        self.vm.ip()
+    }
+
+    pub fn is_halt(&self) -> bool {
+        self.vm.is_halt()
+    }
+
+    /// Values sent with `snd` in the message dialect.
+    pub fn sent(&self) -> usize {
+        self.sent
+    }
+
+    /// How many times the instruction at each address ran.
+    pub fn executed(&self) -> &[u64] {
+        self.vm.executed()
+    }
+
+    /// How many times instructions of kind `op` ran.
+    pub fn count(&self, op: Op) -> u64 {
+        self.vm.program().iter().zip(self.executed().iter()).filter(|(i, _)| i.op == op).map(|(_, n)| n).sum()
+    }
+
+    pub fn receive(&mut self, val: i64) {
+        self.vm.add_input(val);
+    }
+
+    pub fn step(&mut self) -> Step {
+        // halting on the last instruction is only reported on the next step
+        if self.vm.is_halt() {
+            return Step::Halt;
+        }
+
+        match self.vm.step() {
+            vm::Step::Ok | vm::Step::Halt => Step::Ok,
+            vm::Step::Blocked => Step::Blocked,
+            vm::Step::Output(val) => match self.dialect {
+                Dialect::Sound => Step::Recover(val),
+                Dialect::Message => {
+                    self.sent += 1;
+                    Step::Send(val)
+                }
+            },
+        }
+    }
+
+    pub fn run(&mut self) {
+        while !matches!(self.step(), Step::Halt | Step::Blocked) { }
+    }
+
+    /// Runs until the next sound is recovered, or None if the program halts first.
+    pub fn run_until_recover(&mut self) -> Option<i64> {
+        loop {
+            match self.step() {
+                Step::Recover(val) => return Some(val),
+                Step::Halt | Step::Blocked => return None,
+                _ => (),
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DuetEnd {
+    Halted,
+    /// Both programs are waiting on `rcv` with nothing left to receive, or one is and the
+    /// other has halted.
+    Deadlock,
+}
+
+/// Two copies of a program in the message dialect, with `p` set to their id and each one's
+/// `snd` feeding the other's `rcv`.
+pub struct Duet {
+    machines: [Machine; 2],
+}
+
+impl Duet {
+    pub fn new(machine: &Machine) -> Self {
+        let machines = [0, 1].map(|id| {
+            let mut machine = machine.with_dialect(Dialect::Message);
+            machine.set_reg('p', id);
+            machine
+        });
+        Self { machines }
+    }
+
+    pub fn machine(&self, id: usize) -> &Machine {
+        &self.machines[id]
+    }
+
+    /// Runs each program in turn until it blocks or halts, stopping once neither can make
+    /// progress.
+    pub fn run(&mut self) -> DuetEnd {
+        loop {
+            let mut progress = false;
+            for id in 0..2 {
+                loop {
+                    match self.machines[id].step() {
+                        Step::Ok | Step::Recover(_) => (),
+                        Step::Send(val) => self.machines[1 - id].receive(val),
+                        Step::Blocked | Step::Halt => break,
+                    }
+                    progress = true;
+                }
+            }
+
+            if !progress {
+                return if self.machines.iter().all(Machine::is_halt) { DuetEnd::Halted } else { DuetEnd::Deadlock };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Dialect, Duet, DuetEnd, Machine, Op};
+
+    #[test]
+    fn counters() {
+        let mut machine = Machine::from_string("
+            set a 3
+            mul b a
+            sub a 1
+            jnz a -2", Dialect::Sound);
+        machine.run();
+        assert_eq!(machine.executed(), &[1, 3, 3, 3]);
+        assert_eq!(machine.count(Op::Mul), 3);
+    }
+
+    #[test]
+    fn duet() {
+        let machine = Machine::from_string("
+            snd 1
+            snd 2
+            snd p
+            rcv a
+            rcv b
+            rcv c
+            rcv d", Dialect::Sound);
+        let mut duet = Duet::new(&machine);
+        assert_eq!(duet.run(), DuetEnd::Deadlock);
+        assert_eq!(duet.machine(1).reg('c'), 0);
+        assert_eq!(duet.machine(0).reg('c'), 1);
+        assert_eq!(duet.machine(1).sent(), 3);
+        assert_eq!(duet.machine(0).ip(), 6);
+    }
+}
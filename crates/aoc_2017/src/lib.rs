@@ -1,5 +1,6 @@
 #![cfg(test)]
 
+mod duet;
 mod knot_hash;
 
 mod day01;
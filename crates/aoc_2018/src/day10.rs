@@ -1,4 +1,4 @@
-use aoc_common::{file_lines, map_points_to_string, IteratorExt, Vec2i32};
+use aoc_common::{file_lines, ocr, IteratorExt, Vec2i32};
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -51,21 +51,8 @@ fn run() -> (Vec<Vec2i32>, i32) {
 #[test]
 fn part1() {
     let (points, _) = run();
-    let answer = map_points_to_string(points.into_iter());
-
-    let expected = "
-█████   █    █  ██████  ██████   ████   ██████  ██████  █    █
-█    █  █    █  █            █  █    █  █            █  █    █
-█    █  █    █  █            █  █       █            █   █  █ 
-█    █  █    █  █           █   █       █           █    █  █ 
-█████   ██████  █████      █    █       █████      █      ██  
-█       █    █  █         █     █       █         █       ██  
-█       █    █  █        █      █       █        █       █  █ 
-█       █    █  █       █       █       █       █        █  █ 
-█       █    █  █       █       █    █  █       █       █    █
-█       █    █  █       ██████   ████   ██████  ██████  █    █";
-
-    assert_eq!(expected, answer);
+    let answer = ocr(points.into_iter()).unwrap();
+    assert_eq!(answer, "PHFZCEZX");
 }
 
 #[test]
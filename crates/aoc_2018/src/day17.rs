@@ -1,6 +1,4 @@
-use std::{fs::File, io::Write};
-
-use aoc_common::{file_string, IteratorExt, Vec2us};
+use aoc_common::{file_string, Grid, Image, IteratorExt, Rgb, Vec2us};
 use regex::RegexBuilder;
 
 fn input() -> (Vec<Vec<u8>>, Vec2us) {
@@ -66,16 +64,15 @@ fn input() -> (Vec<Vec<u8>>, Vec2us) {
     (map, source)
 }
 
-fn _print_map(map: &Vec<Vec<u8>>) -> std::io::Result<()> {
-    let mut f = File::create("map.txt")?;
-
-    for l in map.iter() {
-        writeln!(&mut f, "{}", str::from_utf8(l).unwrap())?;
-    }
-
-    f.flush()?;
-
-    Ok(())
+fn _save_map(map: &[Vec<u8>]) -> std::io::Result<()> {
+    let image = Image::from_grid(&Grid::new(map.to_vec()), |c| match c {
+        b'#' => Rgb(120, 80, 40),
+        b'~' => Rgb(30, 60, 200),
+        b'|' => Rgb(120, 180, 255),
+        b'+' => Rgb(255, 0, 0),
+        _ => Rgb(235, 225, 200),
+    });
+    image.scaled(2).save("map.png")
 }
 
 fn explore(map: &mut Vec<Vec<u8>>, pos: Vec2us) -> bool {
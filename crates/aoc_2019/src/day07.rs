@@ -1,37 +1,25 @@
 use aoc_common::full_permutations;
 
-use intcode::{IntCode, IntCodeResult};
-
-fn run_once(phases: &[&i64]) -> i64 {
-    let mut computers = vec![IntCode::from_file("inputs/day07.txt"); 5];
-    for pair in computers.iter_mut().zip(phases) {
-        pair.0.push_input_back(**pair.1);
-    }
-
-    let mut result = 0;
-    for c in computers.iter_mut() {
-        result = c.run_input(&[result]).unwrap();
-    }
-
-    result
-}
-
-fn run_multiple(phases: &[&i64]) -> i64 {
-    let mut computers = vec![IntCode::from_file("inputs/day07.txt"); 5];
-    for pair in computers.iter_mut().zip(phases) {
-        pair.0.push_input_back(**pair.1);
-    }
-
-    let mut result = 0;
-    while computers.iter().any(|c| !c.is_halt()) {
-        for c in computers.iter_mut() {
-            if let IntCodeResult::Output(o) = c.run_input(&[result]) {
-                result = o;
-            }
+use intcode::{network::{Channel, Event, Network}, IntCode};
+
+fn amplifiers(phases: &[&i64], feedback: bool) -> i64 {
+    let mut network = Network::new(vec![IntCode::from_file("inputs/day07.txt"); phases.len()]);
+    for (i, phase) in phases.iter().enumerate() {
+        network.send(i, &[**phase]);
+        if i + 1 < phases.len() {
+            network.connect(i, Channel::Pipe(i + 1));
+        } else if feedback {
+            network.connect(i, Channel::Pipe(0));
         }
     }
-
-    result
+    network.send(0, &[0]);
+
+    let last = phases.len() - 1;
+    network.run_until(|network, event| match event {
+        Event::Output { value, .. } => Some(value),
+        Event::Halted => network.last_output(last),
+        _ => None,
+    }).unwrap()
 }
 
 #[test]
@@ -40,7 +28,7 @@ fn part1() {
 
     let mut max = 0;
     for current in permutations {
-        let answer = run_once(&current);
+        let answer = amplifiers(&current, false);
         if answer > max {
             max = answer;
         }
@@ -55,11 +43,11 @@ fn part2() {
 
     let mut max = 0;
     for current in permutations {
-        let answer = run_multiple(&current);
+        let answer = amplifiers(&current, true);
         if answer > max {
             max = answer;
         }
     }
 
     assert_eq!(max, 27561242);
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use aoc_common::{file_string, Vec2us, map_points_to_string};
+use aoc_common::{file_string, Vec2us, ocr};
 
 const DIMENSIONS: Vec2us = Vec2us::new(25, 6);
 const AREA: usize = DIMENSIONS.x * DIMENSIONS.y;
@@ -59,15 +59,6 @@ fn part2() {
         .collect::<Vec<_>>()
         ;
 
-    let answer = map_points_to_string(points.into_iter());
-
-    let known = "
- ██  ████  ██  █  █  ██ 
-█  █ █    █  █ █  █ █  █
-█    ███  █    █  █ █   
-█    █    █    █  █ █ ██
-█  █ █    █  █ █  █ █  █
- ██  █     ██   ██   ███";
-
-     assert_eq!(answer, known);
+    let answer = ocr(points.into_iter()).unwrap();
+    assert_eq!(answer, "CFCUG");
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use aoc_common::{Vec2i32, map_points_to_string};
+use aoc_common::{Vec2i32, ocr};
 
 use intcode::{IntCode, IntCodeResult};
 
@@ -48,21 +48,12 @@ fn part1() {
 fn part2() {
     let map = run(true);
 
-    let answer = map_points_to_string(map.iter().filter_map(|p| {
+    let answer = ocr(map.iter().filter_map(|p| {
         if *p.1 {
             Some(p.0)
         } else {
             None
         }
-    }).copied());
-
-    let known = "
- ██  ███  ████ █  █ ███  █  █ ███  ███ 
-█  █ █  █ █    █ █  █  █ █ █  █  █ █  █
-█  █ █  █ ███  ██   █  █ ██   ███  █  █
-████ ███  █    █ █  ███  █ █  █  █ ███ 
-█  █ █    █    █ █  █ █  █ █  █  █ █ █ 
-█  █ █    █    █  █ █  █ █  █ ███  █  █";
-
-    assert_eq!(answer, known);
+    }).copied()).unwrap();
+    assert_eq!(answer, "APFKRKBR");
 }
\ No newline at end of file
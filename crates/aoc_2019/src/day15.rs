@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
 use aoc_common::{Vec2i64, PriorityQueue};
 
@@ -10,7 +10,6 @@ const DIRS: [Vec2i64; 4] = [
     Vec2i64::new(-1,  0),
     Vec2i64::new( 1,  0),
 ];
-const OPPOSITES: [i64; 4] = [2, 1, 4, 3];
 
 fn explore() -> (HashSet<Vec2i64>, Vec2i64) {
     let mut robot = IntCode::from_file("inputs/day15.txt");
@@ -18,30 +17,30 @@ fn explore() -> (HashSet<Vec2i64>, Vec2i64) {
     let start = Vec2i64::zero();
     map.insert(start);
     let mut target = Vec2i64::zero();
-    explore_recurse(&mut robot, &mut map, start, &mut target);
-    (map, target)
-}
 
-fn explore_recurse(robot: &mut IntCode, map: &mut HashSet<Vec2i64>, pos: Vec2i64, target: &mut Vec2i64) {
-    for cand in 0..4 {
-        let new_pos = pos + DIRS[cand as usize];
-        if map.contains(&new_pos) {
-            continue;
-        }
+    // every reachable position keeps a snapshot of the robot standing there, branching from it
+    // only copies the memory pages the droid writes to
+    let mut to_visit = VecDeque::from([(start, robot.snapshot())]);
+    while let Some((pos, snapshot)) = to_visit.pop_front() {
+        for cand in 0..4 {
+            let new_pos = pos + DIRS[cand as usize];
+            if map.contains(&new_pos) {
+                continue;
+            }
 
-        match robot.run_input(&[cand + 1]).unwrap() {
-            result @ (1 | 2) => {
+            robot.restore(&snapshot);
+            if let result @ (1 | 2) = robot.run_input(&[cand + 1]).unwrap() {
                 if result == 2 {
-                    *target = new_pos;
+                    target = new_pos;
                 }
 
                 map.insert(new_pos);
-                explore_recurse(robot, map, new_pos, target);
-                robot.run_input(&[OPPOSITES[cand as usize]]).unwrap();
+                to_visit.push_back((new_pos, robot.snapshot()));
             }
-            _ => ()
         }
     }
+
+    (map, target)
 }
 
 #[test]
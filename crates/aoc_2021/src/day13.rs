@@ -1,6 +1,6 @@
 use std::{collections::HashSet};
 
-use aoc_common::{file_lines, map_points_to_string, Vec2us};
+use aoc_common::{file_lines, ocr, Vec2us};
 
 #[derive(Copy, Clone, PartialEq)]
 enum Fold {
@@ -80,15 +80,6 @@ fn part2() {
         fold_paper(&mut paper, &fold);
     }
 
-    let answer = map_points_to_string(paper.iter().copied());
-
-    let known = "
-█  █ ████  ██  ███  ████ █  █ ███  ███ 
-█  █ █    █  █ █  █    █ █ █  █  █ █  █
-████ ███  █    █  █   █  ██   █  █ █  █
-█  █ █    █    ███   █   █ █  ███  ███ 
-█  █ █    █  █ █ █  █    █ █  █    █ █ 
-█  █ ████  ██  █  █ ████ █  █ █    █  █";
-
-    assert_eq!(answer, known);
+    let answer = ocr(paper.iter().copied()).unwrap();
+    assert_eq!(answer, "HECRZKPR");
 }
\ No newline at end of file
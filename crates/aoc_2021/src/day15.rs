@@ -1,6 +1,4 @@
-use std::{collections::{HashSet}};
-
-use aoc_common::{file_lines, Vec2us, PriorityQueue};
+use aoc_common::{file_lines, search::dijkstra, Vec2us};
 
 fn input() -> Vec<Vec<usize>> {
     file_lines("inputs/day15.txt").map(|l| {
@@ -9,34 +7,11 @@ fn input() -> Vec<Vec<usize>> {
 }
 
 fn search(map: &Vec<Vec<usize>>) -> usize {
-    let mut to_visit: PriorityQueue<Vec2us, usize> = PriorityQueue::new();
-
-    let mut visited: HashSet<Vec2us> = HashSet::new();
-
     let bounds = Vec2us::new(map[0].len(), map.len());
     let target = Vec2us::new(bounds.x - 1, bounds.y - 1);
 
-    to_visit.enqueue(Vec2us::zero(), 0);
-
-    while let Some((current, dist)) = to_visit.dequeue_with_priority() {
-        if current == target {
-            return dist;
-        }
-
-        if visited.contains(&current) {
-            continue;
-        }
-        visited.insert(current);
-
-        for adj in current.adjacent_bounded(&bounds) {
-            if visited.contains(&adj) {
-                continue;
-            }
-            to_visit.enqueue(adj, dist + map[adj.y][adj.x] + 1);
-        }
-    }
-
-    panic!();
+    let successors = |current: &Vec2us| current.adjacent_bounded(&bounds).map(|adj| (adj, map[adj.y][adj.x] + 1));
+    dijkstra(Vec2us::zero(), successors, |current| *current == target).unwrap().cost
 }
 
 #[test]
@@ -1,5 +1,5 @@
-use std::collections::HashSet;
-use aoc_common::{file_lines, IteratorExt, Vec2i32, map_points_to_string};
+use std::{collections::HashSet, str::FromStr};
+use aoc_common::{vm::{Flow, Instruction, Machine}, Vec2i32, ocr};
 use Instr::*;
 
 enum Instr {
@@ -7,38 +7,45 @@ enum Instr {
     Nop,
 }
 
-fn input() -> impl Iterator<Item = Instr> {
-    file_lines("inputs/day10.txt").map(|l| {
-        let split = l.split(' ').to_vec();
-        match split[0] {
-            "addx" => Add(split[1].parse().unwrap()),
-            "noop" => Nop,
-            _ => panic!()
+impl Instruction for Instr {
+    type Word = i32;
+    const REGISTERS: usize = 1;
+
+    fn execute(&self, regs: &mut [i32]) -> Flow<i32> {
+        if let Add(x) = self {
+            regs[0] += x;
         }
-    })
-}
+        Flow::Next
+    }
 
-fn run<F>(mut f: F)
-    where F: FnMut(i32, i32)
-{
-    let mut reg_x = 1;
-    let mut ticks = 1;
+    fn cycles(&self) -> u64 {
+        match self {
+            Add(_) => 2,
+            Nop => 1,
+        }
+    }
+}
 
-    for instr in input() {
-        f(ticks, reg_x);
-        ticks += 1;
+impl FromStr for Instr {
+    type Err = String;
 
-        match instr {
-            Add(x) => {
-                f(ticks, reg_x);
-                ticks += 1;
-                reg_x += x;
-            }
-            Nop => (),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(' ') {
+            Some(("addx", x)) => x.parse().map(Add).map_err(|_| format!("invalid operand '{}'", x)),
+            None if s == "noop" => Ok(Nop),
+            _ => Err(format!("unknown instruction '{}'", s)),
         }
     }
 }
 
+fn run<F>(mut f: F)
+    where F: FnMut(i32, i32)
+{
+    let mut machine: Machine<Instr> = Machine::from_file("inputs/day10.txt");
+    machine.regs_mut()[0] = 1;
+    machine.run_cycles(|ticks, regs| f(ticks as i32, regs[0]));
+}
+
 #[test]
 fn part1() {
     let checks = vec![20, 60, 100, 140, 180, 220];
@@ -78,15 +85,6 @@ fn part2() {
         }
     });
 
-    let answer = map_points_to_string(screen.iter().copied());
-
-    let known = "
-███    ██ ████ ███  ███  ████ ████  ██ 
-█  █    █ █    █  █ █  █ █    █    █  █
-█  █    █ ███  █  █ █  █ ███  ███  █   
-███     █ █    ███  ███  █    █    █   
-█ █  █  █ █    █ █  █    █    █    █  █
-█  █  ██  ████ █  █ █    ████ █     ██ ";
-
-    assert_eq!(answer, known);
+    let answer = ocr(screen.iter().copied()).unwrap();
+    assert_eq!(answer, "RJERPEFC");
 }
\ No newline at end of file
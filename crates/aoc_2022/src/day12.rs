@@ -1,5 +1,4 @@
-use std::collections::VecDeque;
-use aoc_common::{Vec2us, file_lines, IteratorExt};
+use aoc_common::{Vec2us, file_lines, search::bfs, IteratorExt};
 
 fn input() -> (Vec<Vec<i32>>, Vec2us, Vec2us) {
     let mut start = Vec2us::zero();
@@ -30,32 +29,18 @@ fn input() -> (Vec<Vec<i32>>, Vec2us, Vec2us) {
     (map, start, end)
 }
 
-fn search<FFound, FTest>(map: &Vec<Vec<i32>>, start: Vec2us, f_found: FFound, f_test: FTest) -> i32
+fn search<FFound, FTest>(map: &Vec<Vec<i32>>, start: Vec2us, f_found: FFound, f_test: FTest) -> usize
     where
         FFound: Fn(Vec2us, i32) -> bool,
         FTest: Fn(i32, i32) -> bool
 {
     let bounds = Vec2us::new(map[0].len(), map.len());
-    let mut queue: VecDeque<(Vec2us, i32)> = VecDeque::new();
-    let mut visited = vec![vec![false; map[0].len()]; map.len()];
-    visited[start.y][start.x] = true;
+    let height = |p: &Vec2us| map[p.y][p.x];
 
-    queue.push_back((start, 0));
-
-    while let Some((current, dist)) = queue.pop_front() {
-        if f_found(current, map[current.y][current.x]) {
-            return dist;
-        }
-
-        for adj in current.adjacent_bounded(&bounds) {
-            if !visited[adj.y][adj.x] && f_test(map[current.y][current.x], map[adj.y][adj.x]) {
-                visited[adj.y][adj.x] = true;
-                queue.push_back((adj, dist + 1));
-            }
-        }
-    }
-
-    panic!()
+    let successors = |current: &Vec2us| {
+        current.adjacent_bounded(&bounds).filter(|adj| f_test(height(current), height(adj))).to_vec()
+    };
+    bfs(start, successors, |current| f_found(*current, height(current))).unwrap().cost
 }
 
 #[test]
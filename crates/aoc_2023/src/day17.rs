@@ -1,4 +1,4 @@
-use aoc_common::{file_lines, IteratorExt, Vec2i32, PriorityQueue};
+use aoc_common::{file_lines, BucketQueue, IteratorExt, Vec2i32};
 
 fn input() -> Vec<Vec<i32>> {
     file_lines("inputs/day17.txt").map(|l| l.bytes().map(|b| (b - b'0') as i32).to_vec()).to_vec()
@@ -24,7 +24,7 @@ fn dir_to_index(dir: Vec2i32) -> usize {
 
 fn run<const MIN: i32, const MAX: usize>() -> i32 {
     let map = input();
-    let mut queue: PriorityQueue<State, i32> = PriorityQueue::new();
+    let mut queue: BucketQueue<State> = BucketQueue::new();
     let bounds = Vec2i32::new(map[0].len() as i32, map.len() as i32);
     let target = bounds - Vec2i32::new(1, 1);
     let mut nexts: Vec<(Vec2i32, Vec2i32, i32)> = Vec::with_capacity(3);
@@ -38,7 +38,7 @@ fn run<const MIN: i32, const MAX: usize>() -> i32 {
         sum: map[0][1],
     };
     let weight = start_right.sum + start_right.pos.manhattan_from(target);
-    queue.enqueue(start_right, weight);
+    queue.enqueue(start_right, weight as usize);
 
     let start_left = State {
         pos: Vec2i32::new(0, 1),
@@ -47,7 +47,7 @@ fn run<const MIN: i32, const MAX: usize>() -> i32 {
         sum: map[1][0],
     };
     let weight = start_left.sum + start_left.pos.manhattan_from(target);
-    queue.enqueue(start_left, weight);
+    queue.enqueue(start_left, weight as usize);
 
     while let Some(state) = queue.dequeue() {
         if state.pos == target && state.steps >= MIN {
@@ -81,7 +81,7 @@ fn run<const MIN: i32, const MAX: usize>() -> i32 {
                     sum: state.sum + map[pos.y as usize][pos.x as usize],
                 };
                 let weight = next.sum + next.pos.manhattan_from(target);
-                queue.enqueue(next, weight);
+                queue.enqueue(next, weight as usize);
             }
         }
 
@@ -1,6 +1,6 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
 
-use aoc_common::{file_lines, Grid, PriorityQueue, Vec2us};
+use aoc_common::{file_lines, search::{astar, bfs}, Grid, Vec2us};
 
 const BOUNDS: Vec2us = Vec2us::new(71, 71);
 const END: Vec2us = Vec2us::new(70, 70);
@@ -18,64 +18,15 @@ fn part1() {
         map[p] = true;
     }
 
-    let mut visisted: Grid<bool> = Grid::with_dimensions(BOUNDS);
-    let mut queue: PriorityQueue<(Vec2us, usize), usize> = PriorityQueue::new();
-    queue.enqueue((Vec2us::zero(), 0), 0);
-
-    let mut answer = 0;
-    while let Some((current, dist)) = queue.dequeue() {
-        if current == END {
-            answer = dist;
-            break;
-        }
-
-        if visisted[current] {
-            continue;
-        }
-        visisted[current] = true;
-
-        for p in current.adjacent_bounded(&BOUNDS) {
-            if !visisted[p] && !map[p] {
-                queue.enqueue((p, dist + 1), dist + 1 + p.manhattan_from(END));
-            }
-        }
-    }
+    let successors = |current: &Vec2us| current.adjacent_bounded(&BOUNDS).filter(|p| !map[*p]).map(|p| (p, 1)).collect::<Vec<_>>();
+    let answer = astar(Vec2us::zero(), successors, |p| p.manhattan_from(END), |p| *p == END).unwrap().cost;
 
     assert_eq!(answer, 438);
 }
 
 fn try_find_path(map: &Grid<bool>) -> Option<HashSet<Vec2us>> {
-    let mut visited: Grid<Option<Vec2us>> = Grid::with_dimensions(map.bounds());
-    let mut queue: VecDeque<Vec2us> = VecDeque::new();
-    queue.push_back(Vec2us::zero());
-    visited[Vec2us::zero()] = Some(Vec2us::zero());
-
-    while let Some(p) = queue.pop_front() {
-        if p == END {
-            let mut set: HashSet<Vec2us> = HashSet::new();
-            set.insert(p);
-
-            let mut current = p;
-            loop {
-                let prev = visited[current].unwrap();
-                set.insert(prev);
-                if prev.is_zero() {
-                    break;
-                }
-                current = prev;
-            }
-            return Some(set);
-        }
-
-        for adj in p.adjacent_bounded(&BOUNDS) {
-            if visited[adj].is_none() && !map[adj] {
-                visited[adj] = Some(p);
-                queue.push_back(adj);
-            }
-        }
-    }
-
-    None
+    let successors = |p: &Vec2us| p.adjacent_bounded(&BOUNDS).filter(|adj| !map[*adj]).collect::<Vec<_>>();
+    bfs(Vec2us::zero(), successors, |p| *p == END).map(|path| path.states.into_iter().collect())
 }
 
 #[test]
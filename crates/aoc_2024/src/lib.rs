@@ -1,5 +1,7 @@
 #![cfg(test)]
 
+mod three_bit;
+
 mod day01;
 mod day02;
 mod day03;
@@ -0,0 +1,139 @@
+use aoc_common::{file_lines, vm::{Flow, Instruction, Machine}, IteratorExt};
+
+use Op::*;
+
+/// A combo operand: 0 to 3 are literal, 4 to 6 read registers A to C.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Combo {
+    Lit(u64),
+    Reg(usize),
+}
+
+impl Combo {
+    fn new(operand: u64) -> Self {
+        match operand {
+            0..=3 => Combo::Lit(operand),
+            4..=6 => Combo::Reg(operand as usize - 4),
+            _ => panic!("invalid combo operand {}", operand),
+        }
+    }
+
+    fn resolve(&self, regs: &[u64]) -> u64 {
+        match self {
+            Combo::Lit(val) => *val,
+            Combo::Reg(reg) => regs[*reg],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Adv(Combo),
+    Bxl(u64),
+    Bst(Combo),
+    /// Jumps to a word address, always even so it lands on an instruction.
+    Jnz(u64),
+    Bxc,
+    Out(Combo),
+    Bdv(Combo),
+    Cdv(Combo),
+}
+
+impl Op {
+    pub fn decode(opcode: u64, operand: u64) -> Self {
+        match opcode {
+            0 => Adv(Combo::new(operand)),
+            1 => Bxl(operand),
+            2 => Bst(Combo::new(operand)),
+            3 if operand.is_multiple_of(2) => Jnz(operand),
+            3 => panic!("jump to odd address {} lands between instructions", operand),
+            4 => Bxc,
+            5 => Out(Combo::new(operand)),
+            6 => Bdv(Combo::new(operand)),
+            7 => Cdv(Combo::new(operand)),
+            _ => panic!("invalid opcode {}", opcode),
+        }
+    }
+}
+
+impl Instruction for Op {
+    type Word = u64;
+    const REGISTERS: usize = 3;
+
+    fn execute(&self, regs: &mut [u64]) -> Flow<u64> {
+        let div = |combo: &Combo| u32::try_from(combo.resolve(regs)).ok().and_then(|shift| regs[0].checked_shr(shift)).unwrap_or(0);
+        match self {
+            Adv(combo) => regs[0] = div(combo),
+            Bxl(lit) => regs[1] ^= lit,
+            Bst(combo) => regs[1] = combo.resolve(regs) % 8,
+            Jnz(lit) => if regs[0] != 0 { return Flow::Goto(*lit as usize / 2) },
+            Bxc => regs[1] ^= regs[2],
+            Out(combo) => return Flow::Output(combo.resolve(regs) % 8),
+            Bdv(combo) => regs[1] = div(combo),
+            Cdv(combo) => regs[2] = div(combo),
+        }
+        Flow::Next
+    }
+}
+
+/// Initial registers and the raw program, which is also the output a quine has to produce.
+pub struct Computer {
+    pub regs: [u64; 3],
+    pub program: Vec<u64>,
+}
+
+impl Computer {
+    pub fn from_file(path: &str) -> Self {
+        let lines = file_lines(path).filter(|l| !l.is_empty()).to_vec();
+        let value = |line: &str| line.split_once(": ").unwrap().1.to_string();
+
+        let regs = [0, 1, 2].map(|i| value(&lines[i]).parse().unwrap());
+        let program = value(&lines[3]).split(',').map(|n| n.parse().unwrap()).to_vec();
+        Self { regs, program }
+    }
+
+    /// Panics if the program ends in an opcode without an operand.
+    pub fn machine(&self, a: u64) -> Machine<Op> {
+        let pairs = self.program.chunks_exact(2);
+        if let [opcode] = pairs.remainder() {
+            panic!("opcode {} at the end of the program has no operand", opcode);
+        }
+        let ops = pairs.map(|pair| Op::decode(pair[0], pair[1])).to_vec();
+        let mut machine = Machine::new(ops);
+        machine.regs_mut().copy_from_slice(&[a, self.regs[1], self.regs[2]]);
+        machine
+    }
+
+    pub fn run(&self, a: u64) -> Vec<u64> {
+        self.machine(a).run_outputs()
+    }
+
+    /// The smallest value of register A that makes the program print itself.
+    ///
+    /// This only works for programs built as one loop that prints a single value, shifts A right
+    /// by 3 and jumps back to the start while A isn't 0. Each output then only depends on the
+    /// bits of A not yet shifted out, so A can be built 3 bits at a time starting from the last
+    /// output, backtracking when no 3 bits produce the rest of the program. Returns None for
+    /// any other shape of program or if no such value exists.
+    pub fn find_quine(&self) -> Option<u64> {
+        let ops = self.machine(0).program().to_vec();
+        let outputs = ops.iter().filter(|op| matches!(op, Out(_))).count();
+        let shifts = ops.iter().filter(|op| matches!(op, Adv(_))).collect::<Vec<_>>();
+        if outputs != 1 || shifts != [&Adv(Combo::Lit(3))] || ops.last() != Some(&Jnz(0)) {
+            return None;
+        }
+
+        self.quine_from(0, self.program.len())
+    }
+
+    /// Tries each 3 bit extension of `a` that prints `program[len - 1..]`, smallest first.
+    fn quine_from(&self, a: u64, len: usize) -> Option<u64> {
+        if len == 0 {
+            return Some(a);
+        }
+
+        (0..8).map(|bits| (a << 3) | bits)
+            .filter(|next| self.run(*next) == self.program[len - 1..])
+            .find_map(|next| self.quine_from(next, len - 1))
+    }
+}
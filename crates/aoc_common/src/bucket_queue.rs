@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+/// A min priority queue for small integer priorities that never go below the last one dequeued,
+/// which is the case for Dijkstra, or A* with a consistent heuristic, on non-negative step costs.
+/// Every priority gets its own bucket, so queueing is constant time and dequeueing only has to
+/// skip past empty buckets. Items of equal priority come out last in, first out.
+pub struct BucketQueue<T> {
+    buckets: VecDeque<Vec<T>>,
+    /// Priority of the first bucket.
+    base: usize,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    pub fn new() -> Self {
+        Self { buckets: VecDeque::new(), base: 0, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Panics if `priority` is lower than one already dequeued.
+    pub fn enqueue(&mut self, item: T, priority: usize) {
+        assert!(priority >= self.base, "priority {} is below the last one dequeued, {}", priority, self.base);
+        let idx = priority - self.base;
+        if idx >= self.buckets.len() {
+            self.buckets.resize_with(idx + 1, Vec::new);
+        }
+        self.buckets[idx].push(item);
+        self.len += 1;
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.dequeue_with_priority().map(|(item, _)| item)
+    }
+
+    pub fn dequeue_with_priority(&mut self) -> Option<(T, usize)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        while self.buckets[0].is_empty() {
+            self.buckets.pop_front();
+            self.base += 1;
+        }
+        self.len -= 1;
+        self.buckets[0].pop().map(|item| (item, self.base))
+    }
+
+    /// Empties the queue, allowing any priority again.
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.base = 0;
+        self.len = 0;
+    }
+}
+
+impl<T> Default for BucketQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BucketQueue;
+
+    #[test]
+    fn order() {
+        let mut queue = BucketQueue::new();
+        queue.enqueue('c', 7);
+        queue.enqueue('a', 2);
+        queue.enqueue('b', 2);
+        assert_eq!(queue.len(), 3);
+
+        assert_eq!(queue.dequeue_with_priority(), Some(('b', 2)));
+        queue.enqueue('d', 2);
+        queue.enqueue('e', 3);
+        assert_eq!(queue.dequeue(), Some('d'));
+        assert_eq!(queue.dequeue(), Some('a'));
+        assert_eq!(queue.dequeue_with_priority(), Some(('e', 3)));
+        assert_eq!(queue.dequeue_with_priority(), Some(('c', 7)));
+        assert_eq!(queue.dequeue(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn priority_below_base() {
+        let mut queue = BucketQueue::new();
+        queue.enqueue(1, 5);
+        queue.dequeue();
+        queue.enqueue(2, 4);
+    }
+}
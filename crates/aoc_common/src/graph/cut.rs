@@ -0,0 +1,232 @@
+use std::{cmp::Reverse, collections::{HashMap, VecDeque}, ops::{Add, Sub}};
+
+use num_traits::Zero;
+
+use crate::DensePriorityQueue;
+
+use super::Graph;
+
+/// A split of a graph's nodes in two, with the edges that cross it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cut<W> {
+    /// Total weight of the crossing edges.
+    pub weight: W,
+    /// Both halves, each sorted. For an s-t cut the source's half is first.
+    pub sides: [Vec<usize>; 2],
+    /// The crossing edges, each going from the first side to the second.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl<W> Cut<W> {
+    pub fn sizes(&self) -> (usize, usize) {
+        (self.sides[0].len(), self.sides[1].len())
+    }
+}
+
+impl<T, W> Graph<T, W> {
+    /// The cheapest way to split the graph in two, found with Stoer–Wagner. Edges are treated
+    /// as undirected and their weights mustn't be negative. None with fewer than two nodes.
+    pub fn min_cut(&self) -> Option<Cut<W>>
+        where W: Copy + Ord + Zero + Add<Output = W>
+    {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let mut adjacent: Vec<HashMap<usize, W>> = vec![HashMap::new(); self.len()];
+        for from in self.ids() {
+            for (to, weight) in self.edges(from).iter().filter(|(to, _)| *to != from) {
+                // an undirected graph already holds every edge both ways
+                if self.is_directed() || from < *to {
+                    for (a, b) in [(from, *to), (*to, from)] {
+                        let total = adjacent[a].entry(b).or_insert(W::zero());
+                        *total = *total + *weight;
+                    }
+                }
+            }
+        }
+
+        // every node left stands for the group of original nodes merged into it
+        let mut groups: Vec<Vec<usize>> = self.ids().map(|id| vec![id]).collect();
+        let mut active: Vec<usize> = self.ids().collect();
+        let mut best: Option<(W, Vec<usize>)> = None;
+
+        while active.len() > 1 {
+            // add the node most tightly connected to those already added until none are left,
+            // the last one's connection is the cheapest cut between it and the one before
+            let mut queue = DensePriorityQueue::with_ids(self.len());
+            for id in active.iter() {
+                queue.enqueue(*id, Reverse(W::zero()));
+            }
+            let mut order = Vec::with_capacity(active.len());
+            let mut cut_of_phase = W::zero();
+            while let Some((id, Reverse(weight))) = queue.dequeue_with_priority() {
+                order.push(id);
+                cut_of_phase = weight;
+                for (adj, w) in adjacent[id].iter() {
+                    if let Some(Reverse(current)) = queue.priority_of(adj).copied() {
+                        queue.decrease_priority(adj, Reverse(current + *w));
+                    }
+                }
+            }
+
+            let t = order.pop().unwrap();
+            let s = order.pop().unwrap();
+            if best.as_ref().is_none_or(|(weight, _)| cut_of_phase < *weight) {
+                best = Some((cut_of_phase, groups[t].clone()));
+            }
+
+            for (adj, w) in std::mem::take(&mut adjacent[t]) {
+                adjacent[adj].remove(&t);
+                if adj != s {
+                    let total = adjacent[s].get(&adj).copied().unwrap_or(W::zero()) + w;
+                    adjacent[s].insert(adj, total);
+                    adjacent[adj].insert(s, total);
+                }
+            }
+            let merged = std::mem::take(&mut groups[t]);
+            groups[s].extend(merged);
+            active.retain(|id| *id != t);
+        }
+
+        let (weight, side) = best.unwrap();
+        let mut in_side = vec![false; self.len()];
+        for id in side {
+            in_side[id] = true;
+        }
+        Some(self.cut(weight, &in_side, true))
+    }
+
+    /// The maximum flow from `source` to `sink` with edge weights as capacities, found with
+    /// Edmonds–Karp, and the matching minimum cut: the source's side is everything it can
+    /// still reach with the flow in place. Panics if `source` and `sink` are the same node.
+    pub fn max_flow(&self, source: usize, sink: usize) -> Cut<W>
+        where W: Copy + Ord + Zero + Add<Output = W> + Sub<Output = W>
+    {
+        assert_ne!(source, sink, "max flow needs the source and sink to be different nodes");
+
+        struct Arc<W> {
+            to: usize,
+            capacity: W,
+            /// Index of the opposite arc in `arcs[to]`.
+            rev: usize,
+        }
+
+        let mut arcs: Vec<Vec<Arc<W>>> = self.ids().map(|_| Vec::new()).collect();
+        for from in self.ids() {
+            for (to, capacity) in self.edges(from).iter() {
+                let rev = arcs[*to].len() + (from == *to) as usize;
+                let back = arcs[from].len();
+                arcs[from].push(Arc { to: *to, capacity: *capacity, rev });
+                arcs[*to].push(Arc { to: from, capacity: W::zero(), rev: back });
+            }
+        }
+
+        let mut flow = W::zero();
+        loop {
+            // shortest path with capacity left, remembering the arc used to reach each node
+            let mut via: Vec<Option<(usize, usize)>> = vec![None; self.len()];
+            let mut queue = VecDeque::from([source]);
+            let mut reached = vec![false; self.len()];
+            reached[source] = true;
+            while let Some(current) = queue.pop_front() {
+                for (idx, arc) in arcs[current].iter().enumerate() {
+                    if !reached[arc.to] && arc.capacity > W::zero() {
+                        reached[arc.to] = true;
+                        via[arc.to] = Some((current, idx));
+                        queue.push_back(arc.to);
+                    }
+                }
+            }
+
+            if !reached[sink] {
+                return self.cut(flow, &reached, false);
+            }
+
+            let mut path = Vec::new();
+            let mut current = sink;
+            while let Some((from, idx)) = via[current] {
+                path.push((from, idx));
+                current = from;
+            }
+
+            let bottleneck = path.iter().map(|(from, idx)| arcs[*from][*idx].capacity).min().unwrap();
+            for (from, idx) in path {
+                let arc = &mut arcs[from][idx];
+                arc.capacity = arc.capacity - bottleneck;
+                let (to, rev) = (arc.to, arc.rev);
+                let back = &mut arcs[to][rev];
+                back.capacity = back.capacity + bottleneck;
+            }
+            flow = flow + bottleneck;
+        }
+    }
+
+    fn cut(&self, weight: W, in_side: &[bool], both_ways: bool) -> Cut<W> {
+        let mut edges = Vec::new();
+        for from in self.ids() {
+            for to in self.neighbors(from) {
+                if in_side[from] && !in_side[to] {
+                    edges.push((from, to));
+                } else if both_ways && self.is_directed() && !in_side[from] && in_side[to] {
+                    edges.push((to, from));
+                }
+            }
+        }
+
+        let (side, rest) = self.ids().partition(|id| in_side[*id]);
+        Cut { weight, sides: [side, rest], edges }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::Graph;
+
+    #[test]
+    fn min_cut() {
+        // two triangles joined by a single edge, the first with one light edge
+        let mut graph: Graph = Graph::undirected();
+        for (a, b, w) in [("a", "b", 3), ("b", "c", 1), ("c", "a", 3), ("c", "d", 2), ("d", "e", 4), ("e", "f", 4), ("f", "d", 4)] {
+            graph.add_weighted_edge(a, b, w);
+        }
+
+        let cut = graph.min_cut().unwrap();
+        assert_eq!(cut.weight, 2);
+        assert_eq!(cut.sizes(), (3, 3));
+        let c = graph.id("c").unwrap();
+        let d = graph.id("d").unwrap();
+        assert!(cut.edges == vec![(c, d)] || cut.edges == vec![(d, c)]);
+
+        let mut single: Graph = Graph::undirected();
+        single.node("a");
+        assert_eq!(single.min_cut(), None);
+    }
+
+    #[test]
+    fn max_flow() {
+        let mut graph: Graph<String, i32> = Graph::directed();
+        for (a, b, w) in [("s", "a", 10), ("s", "b", 5), ("a", "b", 15), ("a", "t", 5), ("b", "t", 10)] {
+            graph.add_weighted_edge(a, b, w);
+        }
+        let id = |key: &str| graph.id(key).unwrap();
+
+        let cut = graph.max_flow(id("s"), id("t"));
+        assert_eq!(cut.weight, 15);
+        // everything leaving s is used to capacity
+        assert_eq!(cut.sides, [vec![id("s")], vec![id("a"), id("b"), id("t")]]);
+        assert_eq!(cut.edges, vec![(id("s"), id("a")), (id("s"), id("b"))]);
+
+        let cut = graph.max_flow(id("a"), id("t"));
+        assert_eq!(cut.weight, 15);
+        assert_eq!(cut.sizes(), (2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "different nodes")]
+    fn max_flow_to_itself() {
+        let mut graph: Graph<String, i32> = Graph::directed();
+        graph.add_weighted_edge("s", "t", 1);
+        graph.max_flow(0, 0);
+    }
+}
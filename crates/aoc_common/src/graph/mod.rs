@@ -0,0 +1,423 @@
+use std::{borrow::Borrow, collections::{HashSet, VecDeque}, hash::Hash, ops::{Add, Range}};
+
+use num_traits::{One, Zero};
+
+use crate::IdMap;
+
+pub use cut::*;
+
+mod cut;
+
+/// A graph whose nodes are interned through an `IdMap`, so they're added and looked up by key
+/// while the edges and every algorithm work on dense `usize` ids. Edges carry a weight, 1 unless
+/// added with `add_weighted_edge`.
+pub struct Graph<T = String, W = u64> {
+    nodes: IdMap<T>,
+    edges: Vec<Vec<(usize, W)>>,
+    directed: bool,
+}
+
+impl<T, W> Graph<T, W> {
+    pub fn directed() -> Self {
+        Self { nodes: IdMap::new(), edges: Vec::new(), directed: true }
+    }
+
+    /// A graph where every edge is added in both directions.
+    pub fn undirected() -> Self {
+        Self { nodes: IdMap::new(), edges: Vec::new(), directed: false }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    pub fn ids(&self) -> Range<usize> {
+        0..self.len()
+    }
+
+    pub fn key(&self, id: usize) -> &T {
+        self.nodes.get_key(id)
+    }
+
+    /// Outgoing edges with their weights.
+    pub fn edges(&self, id: usize) -> &[(usize, W)] {
+        &self.edges[id]
+    }
+
+    pub fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges[id].iter().map(|(to, _)| *to)
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.neighbors(from).any(|id| id == to)
+    }
+
+    /// Adds an edge between two existing nodes by id.
+    pub fn add_edge_ids(&mut self, from: usize, to: usize, weight: W)
+        where W: Clone
+    {
+        if !self.directed {
+            self.edges[to].push((from, weight.clone()));
+        }
+        self.edges[from].push((to, weight));
+    }
+
+    /// Groups of nodes connected to each other, ignoring the direction of edges. Each group is
+    /// sorted and the groups are ordered by their smallest id.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut both_ways = self.edges.iter().map(|edges| edges.iter().map(|(to, _)| *to).collect::<Vec<_>>()).collect::<Vec<_>>();
+        if self.directed {
+            for from in self.ids() {
+                for to in self.neighbors(from) {
+                    both_ways[to].push(from);
+                }
+            }
+        }
+
+        let mut visited = vec![false; self.len()];
+        let mut components = Vec::new();
+        for start in self.ids() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(current) = queue.pop_front() {
+                for adj in both_ways[current].iter() {
+                    if !visited[*adj] {
+                        visited[*adj] = true;
+                        component.push(*adj);
+                        queue.push_back(*adj);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Orders the nodes so every edge points forward, or None if there's a cycle. Ties are
+    /// broken by lowest id first. An undirected graph with any edge has a cycle.
+    pub fn toposort(&self) -> Option<Vec<usize>> {
+        let mut incoming = vec![0; self.len()];
+        for from in self.ids() {
+            for to in self.neighbors(from) {
+                incoming[to] += 1;
+            }
+        }
+
+        let mut queue: VecDeque<usize> = self.ids().filter(|id| incoming[*id] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(current) = queue.pop_front() {
+            order.push(current);
+            for to in self.neighbors(current) {
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// Number of distinct paths from `from` to `to`, or None if the graph has a cycle or there
+    /// are too many paths to count in a u64.
+    pub fn count_paths(&self, from: usize, to: usize) -> Option<u64> {
+        let order = self.toposort()?;
+        let mut ways = vec![0u64; self.len()];
+        ways[from] = 1;
+        for current in order {
+            if ways[current] == 0 {
+                continue;
+            }
+            for next in self.neighbors(current) {
+                ways[next] = ways[next].checked_add(ways[current])?;
+            }
+        }
+        Some(ways[to])
+    }
+
+    /// Tarjan's algorithm. Components come out sinks first, so every edge between two of them
+    /// points from a later one to an earlier one.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let mut index: Vec<Option<usize>> = vec![None; self.len()];
+        let mut low = vec![0; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        let mut stack = Vec::new();
+        let mut next = 0;
+        let mut components = Vec::new();
+
+        for root in self.ids() {
+            if index[root].is_some() {
+                continue;
+            }
+
+            // the depth first path kept by hand, with how many edges each node has followed,
+            // so long chains don't overflow the call stack
+            let mut path = vec![(root, 0)];
+            while let Some((id, mut pos)) = path.pop() {
+                if pos == 0 {
+                    index[id] = Some(next);
+                    low[id] = next;
+                    next += 1;
+                    stack.push(id);
+                    on_stack[id] = true;
+                } else {
+                    let child = self.edges(id)[pos - 1].0;
+                    low[id] = low[id].min(low[child]);
+                }
+
+                let edges = self.edges(id);
+                let mut descended = false;
+                while pos < edges.len() {
+                    let to = edges[pos].0;
+                    pos += 1;
+                    match index[to] {
+                        None => {
+                            path.push((id, pos));
+                            path.push((to, 0));
+                            descended = true;
+                            break;
+                        }
+                        Some(to_index) if on_stack[to] => low[id] = low[id].min(to_index),
+                        Some(_) => (),
+                    }
+                }
+                if descended {
+                    continue;
+                }
+
+                if Some(low[id]) == index[id] {
+                    let mut component = Vec::new();
+                    loop {
+                        let top = stack.pop().unwrap();
+                        on_stack[top] = false;
+                        component.push(top);
+                        if top == id {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Every maximal clique, each sorted, found with Bron–Kerbosch with pivoting. Edges are
+    /// treated as undirected.
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        fn recurse(r: &mut Vec<usize>, mut p: HashSet<usize>, mut x: HashSet<usize>, adjacent: &[HashSet<usize>], cliques: &mut Vec<Vec<usize>>) {
+            if p.is_empty() && x.is_empty() {
+                let mut clique = r.clone();
+                clique.sort();
+                cliques.push(clique);
+                return;
+            }
+
+            // anything next to the pivot is found again through one of the others
+            let pivot = *p.union(&x).max_by_key(|u| p.intersection(&adjacent[**u]).count()).unwrap();
+            let candidates: Vec<usize> = p.difference(&adjacent[pivot]).copied().collect();
+            for v in candidates {
+                r.push(v);
+                let next_p = p.intersection(&adjacent[v]).copied().collect();
+                let next_x = x.intersection(&adjacent[v]).copied().collect();
+                recurse(r, next_p, next_x, adjacent, cliques);
+                r.pop();
+
+                p.remove(&v);
+                x.insert(v);
+            }
+        }
+
+        let mut adjacent = vec![HashSet::new(); self.len()];
+        for from in self.ids() {
+            for to in self.neighbors(from).filter(|to| *to != from) {
+                adjacent[from].insert(to);
+                adjacent[to].insert(from);
+            }
+        }
+
+        let mut cliques = Vec::new();
+        recurse(&mut Vec::new(), self.ids().collect(), HashSet::new(), &adjacent, &mut cliques);
+        cliques
+    }
+
+    /// The largest of the maximal cliques, the first found if several tie.
+    pub fn max_clique(&self) -> Vec<usize> {
+        self.maximal_cliques().into_iter().rev().max_by_key(|c| c.len()).unwrap_or_default()
+    }
+
+    /// Cheapest distance between every pair of nodes, None where there's no path. Weights may be
+    /// negative as long as no cycle is.
+    pub fn floyd_warshall(&self) -> Vec<Vec<Option<W>>>
+        where W: Copy + Ord + Zero + Add<Output = W>
+    {
+        let mut dist = vec![vec![None; self.len()]; self.len()];
+        for from in self.ids() {
+            dist[from][from] = Some(W::zero());
+            for (to, weight) in self.edges[from].iter() {
+                if dist[from][*to].is_none_or(|d| *weight < d) {
+                    dist[from][*to] = Some(*weight);
+                }
+            }
+        }
+
+        for k in self.ids() {
+            for i in self.ids() {
+                let Some(to_k) = dist[i][k] else { continue };
+                for j in self.ids() {
+                    if let Some(from_k) = dist[k][j] {
+                        let through = to_k + from_k;
+                        if dist[i][j].is_none_or(|d| through < d) {
+                            dist[i][j] = Some(through);
+                        }
+                    }
+                }
+            }
+        }
+        dist
+    }
+}
+
+impl<T: Eq + Hash, W> Graph<T, W> {
+    pub fn id<U>(&self, key: &U) -> Option<usize>
+        where T: Borrow<U>, U: Eq + Hash + ?Sized
+    {
+        self.nodes.get(key)
+    }
+
+    /// The id of the node, adding it if it's new.
+    pub fn node<U>(&mut self, key: &U) -> usize
+        where T: Borrow<U>, U: Eq + Hash + ToOwned<Owned = T> + ?Sized
+    {
+        let id = self.nodes.get_or_insert(key);
+        if id == self.edges.len() {
+            self.edges.push(Vec::new());
+        }
+        id
+    }
+
+    pub fn add_weighted_edge<U>(&mut self, from: &U, to: &U, weight: W)
+        where T: Borrow<U>, U: Eq + Hash + ToOwned<Owned = T> + ?Sized, W: Clone
+    {
+        let from = self.node(from);
+        let to = self.node(to);
+        self.add_edge_ids(from, to, weight);
+    }
+
+    pub fn add_edge<U>(&mut self, from: &U, to: &U)
+        where T: Borrow<U>, U: Eq + Hash + ToOwned<Owned = T> + ?Sized, W: Clone + One
+    {
+        self.add_weighted_edge(from, to, W::one());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Graph;
+
+    fn directed(edges: &[(&str, &str)]) -> Graph {
+        let mut graph = Graph::directed();
+        for (from, to) in edges {
+            graph.add_edge(*from, *to);
+        }
+        graph
+    }
+
+    #[test]
+    fn components() {
+        let mut graph: Graph = Graph::undirected();
+        for (a, b) in [("a", "b"), ("c", "d"), ("b", "e"), ("f", "f")] {
+            graph.add_edge(a, b);
+        }
+        assert_eq!(graph.len(), 6);
+        assert!(graph.has_edge(graph.id("b").unwrap(), graph.id("a").unwrap()));
+        assert_eq!(graph.components(), vec![vec![0, 1, 4], vec![2, 3], vec![5]]);
+
+        // weakly connected for directed graphs
+        assert_eq!(directed(&[("a", "b"), ("c", "b")]).components(), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn dag() {
+        let graph = directed(&[("you", "a"), ("you", "b"), ("a", "c"), ("b", "c"), ("a", "out"), ("c", "out"), ("b", "d")]);
+        let order = graph.toposort().unwrap();
+        assert_eq!(order.iter().map(|id| graph.key(*id).as_str()).collect::<Vec<_>>(), vec!["you", "a", "b", "c", "d", "out"]);
+        assert_eq!(graph.count_paths(graph.id("you").unwrap(), graph.id("out").unwrap()), Some(3));
+
+        let cyclic = directed(&[("a", "b"), ("b", "a")]);
+        assert_eq!(cyclic.toposort(), None);
+        assert_eq!(cyclic.count_paths(0, 1), None);
+
+        // a row of diamonds doubles the paths at every one
+        let diamonds = |n: usize| {
+            let mut graph: Graph<usize> = Graph::directed();
+            for i in 0..n {
+                for side in [1, 2] {
+                    graph.add_edge(&(3 * i), &(3 * i + side));
+                    graph.add_edge(&(3 * i + side), &(3 * i + 3));
+                }
+            }
+            graph.count_paths(graph.id(&0).unwrap(), graph.id(&(3 * n)).unwrap())
+        };
+        assert_eq!(diamonds(63), Some(1 << 63));
+        assert_eq!(diamonds(64), None);
+    }
+
+    #[test]
+    fn strongly_connected() {
+        let graph = directed(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("d", "e"), ("e", "d"), ("f", "e")]);
+        assert_eq!(graph.strongly_connected_components(), vec![vec![3, 4], vec![0, 1, 2], vec![5]]);
+
+        // deep enough to overflow the stack if every node took a call
+        let mut chain: Graph<usize> = Graph::directed();
+        for i in 1..200_000 {
+            chain.add_edge(&(i - 1), &i);
+        }
+        chain.add_edge(&199_999, &0);
+        assert_eq!(chain.strongly_connected_components(), vec![(0..200_000).collect::<Vec<_>>()]);
+    }
+
+    #[test]
+    fn cliques() {
+        let mut graph: Graph = Graph::undirected();
+        for edge in ["kh-tc", "qp-kh", "de-cg", "ka-co", "yn-aq", "qp-ub", "cg-tb", "vc-aq", "tb-ka", "wh-tc", "yn-cg",
+            "kh-ub", "ta-co", "de-co", "tc-td", "tb-wq", "wh-td", "ta-ka", "td-qp", "aq-cg", "wq-ub", "ub-vc", "de-ta",
+            "wq-aq", "wq-vc", "wh-yn", "ka-de", "kh-ta", "co-tc", "wh-qp", "tb-vc", "td-yn"] {
+            let (a, b) = edge.split_once('-').unwrap();
+            graph.add_edge(a, b);
+        }
+
+        let mut best: Vec<&str> = graph.max_clique().into_iter().map(|id| graph.key(id).as_str()).collect();
+        best.sort();
+        assert_eq!(best.join(","), "co,de,ka,ta");
+        assert!(graph.maximal_cliques().iter().all(|c| c.iter().all(|a| c.iter().all(|b| a == b || graph.has_edge(*a, *b)))));
+    }
+
+    #[test]
+    fn floyd_warshall() {
+        let mut graph: Graph<String, i32> = Graph::directed();
+        graph.add_weighted_edge("a", "b", 4);
+        graph.add_weighted_edge("a", "c", 1);
+        graph.add_weighted_edge("c", "b", 2);
+        graph.add_weighted_edge("b", "d", -1);
+
+        let dist = graph.floyd_warshall();
+        assert_eq!(dist[0], vec![Some(0), Some(3), Some(1), Some(2)]);
+        assert_eq!(dist[3][0], None);
+    }
+}
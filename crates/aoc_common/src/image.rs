@@ -0,0 +1,225 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use num_traits::PrimInt;
+
+use crate::{Grid, Grid2, Vec2};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// An RGB raster for looking at puzzle state outside the terminal. Build one from a grid or a
+/// set of points, optionally scale it up, then write it as PNG, PPM or SVG.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self { width, height, pixels: vec![background; width * height] }
+    }
+
+    pub fn from_grid<T, F: Fn(&T) -> Rgb>(grid: &Grid<T>, color: F) -> Self {
+        let pixels = grid.iter().map(color).collect();
+        Self { width: grid.width(), height: grid.height(), pixels }
+    }
+
+    pub fn from_grid2<T, F: Fn(&T) -> Rgb>(grid: &Grid2<T>, color: F) -> Self {
+        let pixels = grid.iter().map(color).collect();
+        Self { width: grid.width(), height: grid.height(), pixels }
+    }
+
+    /// Draws the points in `color` over `background`, cropped to their bounding box.
+    pub fn from_points<T, U>(points: T, color: Rgb, background: Rgb) -> Self
+        where T: Iterator<Item = Vec2<U>>, U: PrimInt
+    {
+        let points: Vec<(i64, i64)> = points.map(|p| (p.x.to_i64().unwrap(), p.y.to_i64().unwrap())).collect();
+        let (Some(min_x), Some(max_x)) = (points.iter().map(|p| p.0).min(), points.iter().map(|p| p.0).max()) else {
+            return Self::new(0, 0, background);
+        };
+        let min_y = points.iter().map(|p| p.1).min().unwrap();
+        let max_y = points.iter().map(|p| p.1).max().unwrap();
+
+        let mut image = Self::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize, background);
+        for (x, y) in points {
+            image.set((x - min_x) as usize, (y - min_y) as usize, color);
+        }
+        image
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Every pixel becomes a `scale` by `scale` block.
+    pub fn scaled(&self, scale: usize) -> Image {
+        let mut image = Image::new(self.width * scale, self.height * scale, Rgb::BLACK);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                image.set(x, y, self.get(x / scale, y / scale));
+            }
+        }
+        image
+    }
+
+    /// Binary PPM (P6).
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for Rgb(r, g, b) in self.pixels.iter() {
+            bytes.extend([*r, *g, *b]);
+        }
+        bytes
+    }
+
+    /// An SVG with one rectangle per run of same colored pixels in a row.
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">\n",
+            self.width, self.height);
+        for y in 0..self.height {
+            let row = &self.pixels[y * self.width..(y + 1) * self.width];
+            let mut x = 0;
+            while x < self.width {
+                let run = row[x..].iter().take_while(|c| **c == row[x]).count();
+                writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\" fill=\"{}\"/>", x, y, run, row[x].to_hex()).unwrap();
+                x += run;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// An 8 bit RGB PNG. The image data is stored without compression, which keeps this free of
+    /// dependencies at the cost of file size.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+
+        // each row starts with filter type 0
+        let mut raw = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw.push(0);
+            for Rgb(r, g, b) in row {
+                raw.extend([*r, *g, *b]);
+            }
+        }
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Writes the image in the format named by the file extension: `png`, `ppm` or `svg`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let bytes = match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => self.to_png(),
+            Some("ppm") => self.to_ppm(),
+            Some("svg") => self.to_svg().into_bytes(),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unsupported image format '{}'", path.display()))),
+        };
+        fs::write(path, bytes)
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// A zlib stream made of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    out.extend(((b << 16) | a).to_be_bytes());
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crc32, Image, Rgb};
+    use crate::{Grid, Vec2i32};
+
+    #[test]
+    fn from_grid() {
+        let grid = Grid::new(vec![vec![b'#', b'.'], vec![b'.', b'#']]);
+        let image = Image::from_grid(&grid, |c| if *c == b'#' { Rgb::WHITE } else { Rgb::BLACK }).scaled(2);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.get(1, 1), Rgb::WHITE);
+        assert_eq!(image.get(2, 1), Rgb::BLACK);
+        assert_eq!(image.get(3, 3), Rgb::WHITE);
+    }
+
+    #[test]
+    fn formats() {
+        let points = [Vec2i32::new(-1, 5), Vec2i32::new(1, 6)];
+        let image = Image::from_points(points.into_iter(), Rgb(255, 0, 0), Rgb::BLACK);
+        assert_eq!((image.width(), image.height()), (3, 2));
+
+        let ppm = image.to_ppm();
+        assert_eq!(&ppm[..11], b"P6\n3 2\n255\n");
+        assert_eq!(&ppm[11..17], &[255, 0, 0, 0, 0, 0]);
+
+        let svg = image.to_svg();
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"1\" height=\"1\" fill=\"#ff0000\"/>"));
+        assert!(svg.contains("<rect x=\"0\" y=\"1\" width=\"2\" height=\"1\" fill=\"#000000\"/>"));
+
+        let png = image.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// Tracks where each item sits in an `IndexedPriorityQueue`'s heap.
+pub trait HeapIndex<K> {
+    fn position(&self, item: &K) -> Option<usize>;
+    fn set_position(&mut self, item: &K, position: usize);
+    fn remove_position(&mut self, item: &K);
+    fn clear(&mut self);
+}
+
+impl<K: Clone + Eq + Hash> HeapIndex<K> for HashMap<K, usize> {
+    fn position(&self, item: &K) -> Option<usize> {
+        self.get(item).copied()
+    }
+
+    fn set_position(&mut self, item: &K, position: usize) {
+        if let Some(found) = self.get_mut(item) {
+            *found = position;
+        } else {
+            self.insert(item.clone(), position);
+        }
+    }
+
+    fn remove_position(&mut self, item: &K) {
+        self.remove(item);
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+}
+
+/// Positions of small dense ids, like the ones `IdMap` hands out, kept in a `Vec` instead of
+/// hashed.
+#[derive(Default)]
+pub struct DenseIndex {
+    positions: Vec<Option<usize>>,
+}
+
+impl HeapIndex<usize> for DenseIndex {
+    fn position(&self, item: &usize) -> Option<usize> {
+        self.positions.get(*item).copied().flatten()
+    }
+
+    fn set_position(&mut self, item: &usize, position: usize) {
+        if *item >= self.positions.len() {
+            self.positions.resize(*item + 1, None);
+        }
+        self.positions[*item] = Some(position);
+    }
+
+    fn remove_position(&mut self, item: &usize) {
+        if let Some(found) = self.positions.get_mut(*item) {
+            *found = None;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.positions.clear();
+    }
+}
+
+/// A min priority queue holding each item at most once. It knows where every item is in the
+/// heap, so an item's priority can be looked up, lowered in place or the item taken out, and
+/// Dijkstra doesn't have to queue duplicates and skip the stale ones.
+pub struct IndexedPriorityQueue<K, P, I = HashMap<K, usize>> {
+    heap: Vec<(K, P)>,
+    index: I,
+}
+
+/// An `IndexedPriorityQueue` over `usize` ids. Start one with `with_ids` or `default`.
+pub type DensePriorityQueue<P> = IndexedPriorityQueue<usize, P, DenseIndex>;
+
+impl<K: Clone + Eq + Hash, P: Ord> IndexedPriorityQueue<K, P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P: Ord> DensePriorityQueue<P> {
+    /// A queue with room for ids below `ids` without growing.
+    pub fn with_ids(ids: usize) -> Self {
+        Self { heap: Vec::with_capacity(ids), index: DenseIndex { positions: vec![None; ids] } }
+    }
+}
+
+impl<K, P, I: Default> Default for IndexedPriorityQueue<K, P, I> {
+    fn default() -> Self {
+        Self { heap: Vec::new(), index: I::default() }
+    }
+}
+
+impl<K, P: Ord, I: HeapIndex<K>> IndexedPriorityQueue<K, P, I> {
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, item: &K) -> bool {
+        self.index.position(item).is_some()
+    }
+
+    pub fn priority_of(&self, item: &K) -> Option<&P> {
+        self.index.position(item).map(|pos| &self.heap[pos].1)
+    }
+
+    /// Adds the item, or lowers its priority if it's already queued with a higher one. Returns
+    /// false if it was queued at `priority` or lower.
+    pub fn enqueue(&mut self, item: K, priority: P) -> bool {
+        if self.contains(&item) {
+            return self.decrease_priority(&item, priority);
+        }
+
+        self.heap.push((item, priority));
+        let pos = self.heap.len() - 1;
+        self.index.set_position(&self.heap[pos].0, pos);
+        self.sift_up(pos);
+        true
+    }
+
+    /// Lowers the priority of a queued item. Returns false if it isn't queued or `priority`
+    /// isn't lower than its current one.
+    pub fn decrease_priority(&mut self, item: &K, priority: P) -> bool {
+        match self.index.position(item) {
+            Some(pos) if priority < self.heap[pos].1 => {
+                self.heap[pos].1 = priority;
+                self.sift_up(pos);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Takes the item out of the queue, returning its priority.
+    pub fn remove(&mut self, item: &K) -> Option<P> {
+        let pos = self.index.position(item)?;
+        Some(self.take(pos).1)
+    }
+
+    pub fn dequeue(&mut self) -> Option<K> {
+        self.dequeue_with_priority().map(|(item, _)| item)
+    }
+
+    pub fn dequeue_with_priority(&mut self) -> Option<(K, P)> {
+        if self.heap.is_empty() {
+            None
+        } else {
+            Some(self.take(0))
+        }
+    }
+
+    pub fn peek(&self) -> Option<&K> {
+        self.heap.first().map(|(item, _)| item)
+    }
+
+    pub fn peek_with_priority(&self) -> Option<(&K, &P)> {
+        self.heap.first().map(|(item, priority)| (item, priority))
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.index.clear();
+    }
+
+    fn take(&mut self, pos: usize) -> (K, P) {
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        let taken = self.heap.pop().unwrap();
+        self.index.remove_position(&taken.0);
+
+        if pos < self.heap.len() {
+            self.sift_down(pos);
+            self.sift_up(pos);
+        }
+        taken
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.index.set_position(&self.heap[a].0, a);
+        self.index.set_position(&self.heap[b].0, b);
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.heap[parent].1 <= self.heap[pos].1 {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let smallest = [2 * pos + 1, 2 * pos + 2].into_iter()
+                .filter(|child| *child < self.heap.len())
+                .fold(pos, |smallest, child| if self.heap[child].1 < self.heap[smallest].1 { child } else { smallest });
+            if smallest == pos {
+                break;
+            }
+            self.swap(pos, smallest);
+            pos = smallest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DensePriorityQueue, IndexedPriorityQueue};
+
+    #[test]
+    fn decrease_and_remove() {
+        let mut queue: IndexedPriorityQueue<&str, i32> = IndexedPriorityQueue::new();
+        assert!(queue.enqueue("a", 5));
+        assert!(queue.enqueue("b", 3));
+        assert!(queue.enqueue("c", 4));
+        assert!(!queue.enqueue("a", 6));
+        assert!(queue.enqueue("a", 1));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.priority_of(&"a"), Some(&1));
+        assert_eq!(queue.peek(), Some(&"a"));
+
+        assert!(!queue.decrease_priority(&"d", 0));
+        assert_eq!(queue.remove(&"b"), Some(3));
+        assert!(!queue.contains(&"b"));
+        assert_eq!(queue.remove(&"b"), None);
+
+        assert_eq!(queue.dequeue_with_priority(), Some(("a", 1)));
+        assert_eq!(queue.dequeue_with_priority(), Some(("c", 4)));
+        assert_eq!(queue.dequeue(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn dense_heap_order() {
+        let mut queue = DensePriorityQueue::with_ids(4);
+        let priorities = [17, 3, 11, 3, 8, 25, 1, 14, 9, 6, 20, 12];
+        for (id, priority) in priorities.iter().enumerate() {
+            queue.enqueue(id, *priority);
+        }
+        queue.decrease_priority(&5, 2);
+        queue.remove(&8);
+
+        let mut order = Vec::new();
+        while let Some((id, priority)) = queue.dequeue_with_priority() {
+            assert!(!queue.contains(&id));
+            order.push(priority);
+        }
+        assert_eq!(order, vec![1, 2, 3, 3, 6, 8, 11, 12, 14, 17, 20]);
+    }
+}
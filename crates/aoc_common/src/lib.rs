@@ -6,13 +6,18 @@ use std::str::FromStr;
 use num_traits::PrimInt;
 
 pub use aabb::*;
+pub use bucket_queue::*;
 pub use grid::*;
 pub use grid2::*;
 pub use id_map::*;
+pub use image::*;
+pub use indexed_priority_queue::*;
 pub use iterator_ext::*;
 pub use linear::*;
+pub use ocr::*;
 pub use ord_wrapper::*;
 pub use priority_queue::*;
+pub use recorder::*;
 pub use rect::*;
 pub use vec2::*;
 pub use vec3::*;
@@ -20,19 +25,29 @@ pub use vecn::*;
 pub use virtual_grid::*;
 
 mod aabb;
+mod bucket_queue;
 mod grid;
 mod grid2;
 mod id_map;
+mod image;
+mod indexed_priority_queue;
 mod iterator_ext;
 mod linear;
+mod ocr;
 mod ord_wrapper;
 mod priority_queue;
+mod recorder;
 mod rect;
 mod vec2;
 mod vec3;
 mod vecn;
 mod virtual_grid;
 
+pub mod graph;
+pub mod search;
+pub mod tour;
+pub mod vm;
+
 pub fn file_string(path: &str) -> String {
     let mut buf = String::new();
     File::open(path).unwrap().read_to_string(&mut buf).unwrap();
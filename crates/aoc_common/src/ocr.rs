@@ -0,0 +1,203 @@
+use std::fmt::{self, Display};
+
+use num_traits::PrimInt;
+
+use crate::{Grid, Vec2};
+
+/// Letters 6 pixels tall, mostly 4 wide.
+const SMALL: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Letters 10 pixels tall and 6 wide.
+const LARGE: [(char, [&str; 10]); 15] = [
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OcrError {
+    /// No font is this many pixels tall.
+    Height(usize),
+    /// Some glyphs didn't match any letter. `text` has a `?` in their place and `glyphs` holds
+    /// their bitmaps, drawn the same way as `map_points_to_string`.
+    Unrecognized { text: String, glyphs: Vec<String> },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Height(height) => write!(f, "no font is {} pixels tall", height),
+            OcrError::Unrecognized { text, glyphs } => {
+                write!(f, "read '{}' but couldn't recognize {} glyph(s):", text, glyphs.len())?;
+                for glyph in glyphs {
+                    write!(f, "\n{}", glyph)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError { }
+
+/// Reads block letters drawn by a set of lit points, in either the 6 or 10 pixel tall font.
+pub fn ocr<T, U>(points: T) -> Result<String, OcrError>
+    where T: Iterator<Item = Vec2<U>>, U: PrimInt
+{
+    let points: Vec<(i64, i64)> = points.map(|p| (p.x.to_i64().unwrap(), p.y.to_i64().unwrap())).collect();
+    let Some(min_x) = points.iter().map(|p| p.0).min() else {
+        return Ok(String::new());
+    };
+    let min_y = points.iter().map(|p| p.1).min().unwrap();
+    let width = (points.iter().map(|p| p.0).max().unwrap() - min_x + 1) as usize;
+    let height = (points.iter().map(|p| p.1).max().unwrap() - min_y + 1) as usize;
+
+    let mut pixels = vec![vec![false; width]; height];
+    for (x, y) in points {
+        pixels[(y - min_y) as usize][(x - min_x) as usize] = true;
+    }
+    read(&pixels)
+}
+
+/// Reads block letters from a grid of lit pixels.
+pub fn ocr_grid(grid: &Grid<bool>) -> Result<String, OcrError> {
+    ocr(grid.enumerate().filter(|(_, lit)| **lit).map(|(p, _)| p))
+}
+
+/// Splits a trimmed image into glyphs at its blank columns and looks each one up. A run of
+/// columns that isn't a letter may be letters drawn touching each other, like the 5 pixel wide
+/// Y on the 2016 screen, so it's also tried as a letter followed by the rest of the run.
+fn read(pixels: &[Vec<bool>]) -> Result<String, OcrError> {
+    let font: Vec<(char, &[&str])> = match pixels.len() {
+        6 => SMALL.iter().map(|(c, rows)| (*c, &rows[..])).collect(),
+        10 => LARGE.iter().map(|(c, rows)| (*c, &rows[..])).collect(),
+        height => return Err(OcrError::Height(height)),
+    };
+
+    let blank = |x: usize| pixels.iter().all(|row| !row[x]);
+    let glyph = |from: usize, to: usize| -> Vec<String> {
+        pixels.iter().map(|row| row[from..to].iter().map(|lit| if *lit { '#' } else { '.' }).collect()).collect()
+    };
+    let letter = |from: usize, to: usize| {
+        let glyph = glyph(from, to);
+        font.iter().find(|(_, rows)| rows.iter().eq(glyph.iter())).map(|(c, _)| *c)
+    };
+
+    let width = pixels[0].len();
+    let mut text = String::new();
+    let mut glyphs = Vec::new();
+    let mut x = 0;
+    while x < width {
+        if blank(x) {
+            x += 1;
+            continue;
+        }
+
+        let end = (x..width).find(|x| blank(*x)).unwrap_or(width);
+        let found = letter(x, end).map(|c| (c, end)).or_else(|| {
+            font.iter().map(|(_, rows)| x + rows[0].len()).filter(|to| *to < end)
+                .filter_map(|to| letter(x, to).map(|c| (c, to)))
+                .max_by_key(|(_, to)| *to)
+        });
+
+        match found {
+            Some((c, to)) => {
+                text.push(c);
+                x = to;
+            }
+            None => {
+                text.push('?');
+                glyphs.push(glyph(x, end).join("\n").replace('#', "█").replace('.', " "));
+                x = end;
+            }
+        }
+    }
+
+    if glyphs.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::Unrecognized { text, glyphs })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ocr, ocr_grid, OcrError, LARGE, SMALL};
+    use crate::{Grid, Vec2i32};
+
+    /// Lit points for `letters` laid out with a blank column between them.
+    fn draw(letters: &[&[&str]]) -> Vec<Vec2i32> {
+        let mut points = Vec::new();
+        let mut left = 0;
+        for rows in letters {
+            for (y, row) in rows.iter().enumerate() {
+                for (x, c) in row.chars().enumerate() {
+                    if c == '#' {
+                        points.push(Vec2i32::new(left + x as i32, y as i32 - 3));
+                    }
+                }
+            }
+            left += rows[0].len() as i32 + 1;
+        }
+        points
+    }
+
+    #[test]
+    fn fonts() {
+        let small: Vec<&[&str]> = SMALL.iter().map(|(_, rows)| &rows[..]).collect();
+        assert_eq!(ocr(draw(&small).into_iter()), Ok("ABCEFGHIJKLOPRSUYZ".to_string()));
+
+        let large: Vec<&[&str]> = LARGE.iter().map(|(_, rows)| &rows[..]).collect();
+        assert_eq!(ocr(draw(&large).into_iter()), Ok("ABCEFGHJKLNPRXZ".to_string()));
+    }
+
+    #[test]
+    fn grid() {
+        let mut grid: Grid<bool> = Grid::new(vec![vec![false; 12]; 8]);
+        for p in draw(&[&SMALL[6].1, &SMALL[7].1]) {
+            grid[((p.x + 2) as usize, (p.y + 4) as usize)] = true;
+        }
+        assert_eq!(ocr_grid(&grid), Ok("HI".to_string()));
+    }
+
+    #[test]
+    fn unrecognized() {
+        let smiley = ["#..#", "#..#", "....", "....", "#..#", ".##."];
+        let err = ocr(draw(&[&SMALL[0].1, &smiley]).into_iter()).unwrap_err();
+        assert_eq!(err, OcrError::Unrecognized { text: "A?".to_string(), glyphs: vec!["█  █\n█  █\n    \n    \n█  █\n ██ ".to_string()] });
+        assert_eq!(err.to_string(), "read 'A?' but couldn't recognize 1 glyph(s):\n█  █\n█  █\n    \n    \n█  █\n ██ ");
+
+        assert_eq!(ocr(draw(&[&["#"]]).into_iter()), Err(OcrError::Height(1)));
+    }
+}
@@ -0,0 +1,325 @@
+use std::{collections::HashMap, fmt::Write as _, fs, io, path::Path};
+
+use crate::{Grid, Grid2, Image, Rgb};
+
+/// Collects the successive states of a simulation for playback. A frame equal to the one
+/// before it only makes that frame last longer, and once `max_frames` distinct frames are held
+/// every further push is dropped, repeats included.
+///
+/// `Recorder<Image>` writes an animated GIF, `Recorder<String>` an asciinema cast.
+pub struct Recorder<T> {
+    frames: Vec<(T, u32)>,
+    max_frames: usize,
+    dropped: usize,
+}
+
+impl<T: PartialEq> Recorder<T> {
+    pub fn new(max_frames: usize) -> Self {
+        Self { frames: Vec::new(), max_frames, dropped: 0 }
+    }
+
+    /// Adds a frame, returning false if it repeated the last one or the recorder is full.
+    pub fn push(&mut self, frame: T) -> bool {
+        // frames may have been dropped since the last one kept, so a repeat can't extend it
+        if self.is_full() {
+            self.dropped += 1;
+            return false;
+        }
+
+        if let Some((last, repeats)) = self.frames.last_mut() {
+            if *last == frame {
+                *repeats += 1;
+                return false;
+            }
+        }
+        self.frames.push((frame, 1));
+        true
+    }
+
+    /// Distinct frames, each with the number of consecutive times it was pushed.
+    pub fn frames(&self) -> &[(T, u32)] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames.len() == self.max_frames
+    }
+
+    /// Frames turned away because the recorder was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+impl Recorder<Image> {
+    pub fn capture_grid<U, F: Fn(&U) -> Rgb>(&mut self, grid: &Grid<U>, color: F) -> bool {
+        self.push(Image::from_grid(grid, color))
+    }
+
+    pub fn capture_grid2<U, F: Fn(&U) -> Rgb>(&mut self, grid: &Grid2<U>, color: F) -> bool {
+        self.push(Image::from_grid2(grid, color))
+    }
+
+    /// An endlessly looping GIF showing each frame for `delay` hundredths of a second per
+    /// time it was pushed. Panics if the frames use more than 256 colors between them.
+    pub fn to_gif(&self, delay: u16) -> Vec<u8> {
+        let mut palette: Vec<Rgb> = Vec::new();
+        let mut index: HashMap<Rgb, u8> = HashMap::new();
+        for (frame, _) in self.frames.iter() {
+            for y in 0..frame.height() {
+                for x in 0..frame.width() {
+                    let color = frame.get(x, y);
+                    index.entry(color).or_insert_with(|| {
+                        palette.push(color);
+                        (palette.len() - 1) as u8
+                    });
+                }
+            }
+        }
+        assert!(palette.len() <= 256, "GIF frames use {} colors, at most 256 fit", palette.len());
+
+        // the color table needs a power of two entries, at least 4 for the LZW code size
+        let bits = (2..=8).find(|bits| palette.len() <= 1 << bits).unwrap();
+        palette.resize(1 << bits, Rgb::BLACK);
+
+        let width = self.frames.iter().map(|(f, _)| f.width()).max().unwrap_or(0) as u16;
+        let height = self.frames.iter().map(|(f, _)| f.height()).max().unwrap_or(0) as u16;
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        gif.extend([0x80 | 0x70 | (bits - 1), 0, 0]);
+        for Rgb(r, g, b) in palette.iter() {
+            gif.extend([*r, *g, *b]);
+        }
+        gif.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for (frame, repeats) in self.frames.iter() {
+            let delay = delay.saturating_mul(u16::try_from(*repeats).unwrap_or(u16::MAX));
+            gif.extend([0x21, 0xf9, 4, 0]);
+            gif.extend(delay.to_le_bytes());
+            gif.extend([0, 0]);
+
+            gif.push(0x2c);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend((frame.width() as u16).to_le_bytes());
+            gif.extend((frame.height() as u16).to_le_bytes());
+            gif.push(0);
+
+            let pixels: Vec<u8> = (0..frame.height())
+                .flat_map(|y| (0..frame.width()).map(move |x| (x, y)))
+                .map(|(x, y)| index[&frame.get(x, y)])
+                .collect();
+            gif.push(bits);
+            for block in lzw(&pixels, bits).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0);
+        }
+
+        gif.push(0x3b);
+        gif
+    }
+
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P, delay: u16) -> io::Result<()> {
+        fs::write(path, self.to_gif(delay))
+    }
+}
+
+impl Recorder<String> {
+    pub fn capture_grid<U, F: Fn(&U) -> char>(&mut self, grid: &Grid<U>, to_char: F) -> bool {
+        let text = grid.rows().map(|row| row.iter().map(&to_char).collect::<String>()).collect::<Vec<_>>().join("\n");
+        self.push(text)
+    }
+
+    pub fn capture_grid2<U, F: Fn(&U) -> char>(&mut self, grid: &Grid2<U>, to_char: F) -> bool {
+        let text = (0..grid.height()).map(|y| grid.row(y).iter().map(&to_char).collect::<String>()).collect::<Vec<_>>().join("\n");
+        self.push(text)
+    }
+
+    /// An asciinema v2 cast that clears the terminal and draws each frame, `delay` seconds
+    /// apart per time it was pushed.
+    pub fn to_cast(&self, delay: f64) -> String {
+        let width = self.frames.iter().flat_map(|(f, _)| f.lines()).map(|l| l.chars().count()).max().unwrap_or(0);
+        let height = self.frames.iter().map(|(f, _)| f.lines().count()).max().unwrap_or(0);
+
+        let mut cast = format!("{{\"version\": 2, \"width\": {}, \"height\": {}}}\n", width, height);
+        let mut time = 0.0;
+        for (frame, repeats) in self.frames.iter() {
+            let output = format!("\x1b[H\x1b[2J{}", frame.replace('\n', "\r\n"));
+            writeln!(cast, "[{:.3}, \"o\", \"{}\"]", time, json_escape(&output)).unwrap();
+            time += delay * *repeats as f64;
+        }
+        cast
+    }
+
+    pub fn save_cast<P: AsRef<Path>>(&self, path: P, delay: f64) -> io::Result<()> {
+        fs::write(path, self.to_cast(delay))
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// GIF flavoured LZW: codes are written least significant bit first, start one bit wider
+/// than the color indices and grow up to 12 bits, after which the table is cleared.
+fn lzw(indices: &[u8], min_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter::default();
+    let mut size = min_size + 1;
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    out.write(clear, size);
+
+    let mut iter = indices.iter();
+    if let Some(first) = iter.next() {
+        let mut prefix = *first as u16;
+        for k in iter {
+            if let Some(code) = table.get(&(prefix, *k)) {
+                prefix = *code;
+                continue;
+            }
+
+            out.write(prefix, size);
+            if next == 4096 {
+                out.write(clear, size);
+                table.clear();
+                next = end + 1;
+                size = min_size + 1;
+            } else {
+                // the decoder adds each entry one code later, so widen once it could see `next`
+                if next == 1 << size {
+                    size += 1;
+                }
+                table.insert((prefix, *k), next);
+                next += 1;
+            }
+            prefix = *k as u16;
+        }
+        out.write(prefix, size);
+    }
+
+    out.write(end, size);
+    out.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    filled: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.filled;
+        self.filled += size;
+        while self.filled >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.filled -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.filled > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Recorder;
+    use crate::{Grid, Image, Rgb};
+
+    #[test]
+    fn dedup_and_cap() {
+        let mut recorder = Recorder::new(2);
+        assert!(recorder.push("a".to_string()));
+        assert!(!recorder.push("a".to_string()));
+        assert!(recorder.push("b".to_string()));
+        assert!(recorder.is_full());
+        assert!(!recorder.push("c".to_string()));
+        assert!(!recorder.push("b".to_string()));
+        assert_eq!(recorder.frames(), &[("a".to_string(), 2), ("b".to_string(), 1)]);
+        assert_eq!(recorder.dropped(), 2);
+    }
+
+    #[test]
+    fn cast() {
+        let mut recorder: Recorder<String> = Recorder::new(10);
+        let mut grid = Grid::new(vec![vec![false, true], vec![true, false]]);
+        recorder.capture_grid(&grid, |on| if *on { '#' } else { '"' });
+        recorder.capture_grid(&grid, |on| if *on { '#' } else { '"' });
+        grid[(0, 0)] = true;
+        recorder.capture_grid(&grid, |on| if *on { '#' } else { '"' });
+
+        let expected = r##"
+{"version": 2, "width": 2, "height": 2}
+[0.000, "o", "\u001b[H\u001b[2J\"#\r\n#\""]
+[1.000, "o", "\u001b[H\u001b[2J##\r\n#\""]
+"##;
+        assert_eq!(recorder.to_cast(0.5), expected[1..]);
+    }
+
+    #[test]
+    fn gif() {
+        let mut recorder = Recorder::new(10);
+        let mut image = Image::new(3, 2, Rgb::BLACK);
+        recorder.push(image.clone());
+        image.set(1, 1, Rgb(255, 0, 0));
+        recorder.push(image);
+
+        let gif = recorder.to_gif(5);
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..11], &[3, 0, 2, 0, 0xf1]);
+        assert_eq!(&gif[13..19], &[0, 0, 0, 255, 0, 0]);
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn long_gif_frame() {
+        let mut recorder = Recorder::new(2);
+        for _ in 0..=u16::MAX as usize {
+            recorder.push(Image::new(1, 1, Rgb::BLACK));
+        }
+        assert_eq!(recorder.frames()[0].1, 1 << 16);
+
+        // the delay saturates instead of wrapping round to 0
+        let gif = recorder.to_gif(1);
+        let control = gif.windows(4).position(|w| w == [0x21, 0xf9, 4, 0]).unwrap();
+        assert_eq!(&gif[control + 4..control + 6], &[0xff, 0xff]);
+    }
+
+    #[test]
+    fn lzw() {
+        // clear, 1, 6, 7 in 3 bits, then 8 and end in 4
+        assert_eq!(super::lzw(&[1; 10], 2), vec![0x8c, 0x8f, 0x05]);
+    }
+}
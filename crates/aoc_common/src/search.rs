@@ -0,0 +1,262 @@
+use std::{collections::{hash_map::Entry, HashMap, HashSet, VecDeque}, hash::Hash};
+
+use num_traits::Zero;
+
+use crate::PriorityQueue;
+
+/// A cheapest route found by a search. `states` runs from the start to the goal, both included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+/// Breadth first search where every step costs 1.
+pub fn bfs<S, I, FN, FG>(start: S, mut successors: FN, mut is_goal: FG) -> Option<Path<S, usize>>
+    where S: Clone + Eq + Hash, I: IntoIterator<Item = S>, FN: FnMut(&S) -> I, FG: FnMut(&S) -> bool
+{
+    let mut parents: HashMap<S, Option<S>> = HashMap::new();
+    parents.insert(start.clone(), None);
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some((current, dist)) = queue.pop_front() {
+        if is_goal(&current) {
+            return Some(Path { cost: dist, states: reconstruct(&parents, current) });
+        }
+
+        for next in successors(&current) {
+            if let Entry::Vacant(entry) = parents.entry(next.clone()) {
+                entry.insert(Some(current.clone()));
+                queue.push_back((next, dist + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Cheapest path search. `successors` gives each neighbouring state with the cost of the step
+/// there, which mustn't be negative.
+pub fn dijkstra<S, C, I, FN, FG>(start: S, successors: FN, is_goal: FG) -> Option<Path<S, C>>
+    where S: Clone + Eq + Hash, C: Copy + Ord + Zero, I: IntoIterator<Item = (S, C)>, FN: FnMut(&S) -> I, FG: FnMut(&S) -> bool
+{
+    astar(start, successors, |_| C::zero(), is_goal)
+}
+
+/// Dijkstra guided by `heuristic`, an estimate of the cost left to a goal. The path is only
+/// guaranteed to be the cheapest if the estimate never overshoots.
+pub fn astar<S, C, I, FN, FH, FG>(start: S, mut successors: FN, mut heuristic: FH, mut is_goal: FG) -> Option<Path<S, C>>
+    where S: Clone + Eq + Hash, C: Copy + Ord + Zero, I: IntoIterator<Item = (S, C)>, FN: FnMut(&S) -> I, FH: FnMut(&S) -> C, FG: FnMut(&S) -> bool
+{
+    let mut best: HashMap<S, (C, Option<S>)> = HashMap::new();
+    best.insert(start.clone(), (C::zero(), None));
+    let mut queue: PriorityQueue<(S, C), C> = PriorityQueue::new();
+    queue.enqueue((start.clone(), C::zero()), heuristic(&start));
+
+    while let Some((current, cost)) = queue.dequeue() {
+        // a cheaper way here was found after this one was queued
+        if best[&current].0 < cost {
+            continue;
+        }
+
+        if is_goal(&current) {
+            let parents: HashMap<S, Option<S>> = best.into_iter().map(|(s, (_, parent))| (s, parent)).collect();
+            return Some(Path { cost, states: reconstruct(&parents, current) });
+        }
+
+        for (next, step) in successors(&current) {
+            let next_cost = cost + step;
+            match best.entry(next.clone()) {
+                Entry::Occupied(mut entry) => {
+                    if entry.get().0 <= next_cost {
+                        continue;
+                    }
+                    entry.insert((next_cost, Some(current.clone())));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((next_cost, Some(current.clone())));
+                }
+            }
+            queue.enqueue((next.clone(), next_cost), next_cost + heuristic(&next));
+        }
+    }
+
+    None
+}
+
+fn reconstruct<S: Clone + Eq + Hash>(parents: &HashMap<S, Option<S>>, goal: S) -> Vec<S> {
+    let mut states = vec![goal];
+    while let Some(Some(parent)) = parents.get(states.last().unwrap()) {
+        states.push(parent.clone());
+    }
+    states.reverse();
+    states
+}
+
+/// Every cheapest path from the start to the goals, kept as the predecessors each state was
+/// reached from at its lowest cost.
+#[derive(Clone, Debug)]
+pub struct AllPaths<S, C> {
+    pub cost: C,
+    /// The goals reached at `cost`.
+    pub goals: Vec<S>,
+    predecessors: HashMap<S, Vec<S>>,
+}
+
+impl<S: Clone + Eq + Hash, C> AllPaths<S, C> {
+    /// The states one step before `state` on a cheapest path to it. Empty for the start.
+    pub fn predecessors(&self, state: &S) -> &[S] {
+        self.predecessors.get(state).map_or(&[], |p| &p[..])
+    }
+
+    /// Every state on at least one cheapest path, the start and goals included.
+    pub fn states(&self) -> HashSet<S> {
+        let mut states: HashSet<S> = self.goals.iter().cloned().collect();
+        let mut queue = self.goals.clone();
+        while let Some(state) = queue.pop() {
+            for prev in self.predecessors(&state) {
+                if states.insert(prev.clone()) {
+                    queue.push(prev.clone());
+                }
+            }
+        }
+        states
+    }
+
+    /// Every cheapest path, start first. There can be exponentially many of them.
+    pub fn paths(&self) -> Vec<Vec<S>> {
+        fn recurse<S: Clone + Eq + Hash, C>(all: &AllPaths<S, C>, path: &mut Vec<S>, paths: &mut Vec<Vec<S>>) {
+            let prevs = all.predecessors(path.last().unwrap());
+            if prevs.is_empty() {
+                paths.push(path.iter().rev().cloned().collect());
+            }
+            for prev in prevs {
+                path.push(prev.clone());
+                recurse(all, path, paths);
+                path.pop();
+            }
+        }
+
+        let mut paths = Vec::new();
+        for goal in self.goals.iter() {
+            recurse(self, &mut vec![goal.clone()], &mut paths);
+        }
+        paths
+    }
+}
+
+/// Like `dijkstra`, but keeps every way of reaching each state at its lowest cost instead of
+/// the first one found, and carries on until all goals at the lowest cost are reached. Step
+/// costs have to be positive or the predecessors could form a cycle.
+pub fn dijkstra_all<S, C, I, FN, FG>(start: S, mut successors: FN, mut is_goal: FG) -> Option<AllPaths<S, C>>
+    where S: Clone + Eq + Hash, C: Copy + Ord + Zero, I: IntoIterator<Item = (S, C)>, FN: FnMut(&S) -> I, FG: FnMut(&S) -> bool
+{
+    let mut best: HashMap<S, C> = HashMap::new();
+    best.insert(start.clone(), C::zero());
+    let mut predecessors: HashMap<S, Vec<S>> = HashMap::new();
+    let mut queue: PriorityQueue<(S, C), C> = PriorityQueue::new();
+    queue.enqueue((start, C::zero()), C::zero());
+
+    let mut found: Option<C> = None;
+    let mut goals = Vec::new();
+    while let Some((current, cost)) = queue.dequeue() {
+        if found.is_some_and(|found| cost > found) {
+            break;
+        }
+        if best[&current] < cost {
+            continue;
+        }
+
+        if is_goal(&current) {
+            found = Some(cost);
+            goals.push(current);
+            continue;
+        }
+
+        for (next, step) in successors(&current) {
+            let next_cost = cost + step;
+            match best.get(&next) {
+                Some(prev_cost) if *prev_cost < next_cost => continue,
+                Some(prev_cost) if *prev_cost == next_cost => {
+                    predecessors.get_mut(&next).unwrap().push(current.clone());
+                }
+                _ => {
+                    best.insert(next.clone(), next_cost);
+                    predecessors.insert(next.clone(), vec![current.clone()]);
+                    queue.enqueue((next, next_cost), next_cost);
+                }
+            }
+        }
+    }
+
+    found.map(|cost| AllPaths { cost, goals, predecessors })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{astar, bfs, dijkstra, dijkstra_all, Path};
+    use crate::{Grid, Vec2us};
+
+    const MAZE: &str = "
+########
+#S...#.#
+#.##.#.#
+#....E.#
+########";
+
+    fn maze() -> (Grid<u8>, Vec2us, Vec2us) {
+        let grid = Grid::new(MAZE[1..].lines().map(|l| l.bytes().collect()).collect());
+        let find = |c: u8| grid.enumerate().find(|(_, v)| **v == c).unwrap().0;
+        (grid.clone(), find(b'S'), find(b'E'))
+    }
+
+    #[test]
+    fn unweighted() {
+        let (grid, start, end) = maze();
+        let open = |p: &Vec2us| p.adjacent().filter(|adj| grid[*adj] != b'#').collect::<Vec<_>>();
+
+        let path = bfs(start, open, |p| *p == end).unwrap();
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.states.len(), 7);
+        assert_eq!(path.states[0], start);
+        assert!(path.states.windows(2).all(|w| w[0].manhattan_from(w[1]) == 1));
+
+        let weighted = |p: &Vec2us| open(p).into_iter().map(|adj| (adj, 1));
+        assert_eq!(dijkstra(start, weighted, |p| *p == end).map(|p| p.cost), Some(6));
+        let guided = astar(start, weighted, |p| p.manhattan_from(end), |p| *p == end).unwrap();
+        assert_eq!(guided.cost, 6);
+
+        assert_eq!(bfs(start, open, |p| *p == Vec2us::zero()), None);
+    }
+
+    #[test]
+    fn weighted() {
+        // going through 'b' is shorter but going around is cheaper
+        let edges = |s: &char| match s {
+            'a' => vec![('b', 10), ('c', 1)],
+            'b' => vec![('e', 1)],
+            'c' => vec![('d', 1)],
+            'd' => vec![('e', 1)],
+            _ => vec![],
+        };
+        assert_eq!(dijkstra('a', edges, |s| *s == 'e'), Some(Path { cost: 3, states: vec!['a', 'c', 'd', 'e'] }));
+        assert_eq!(bfs('a', |s| edges(s).into_iter().map(|(n, _)| n), |s| *s == 'e').unwrap().states, vec!['a', 'b', 'e']);
+    }
+
+    #[test]
+    fn all_paths() {
+        let (grid, start, end) = maze();
+        let open = |p: &Vec2us| p.adjacent().filter(|adj| grid[*adj] != b'#').map(|adj| (adj, 1)).collect::<Vec<_>>();
+
+        let all = dijkstra_all(start, open, |p| *p == end).unwrap();
+        assert_eq!(all.cost, 6);
+        assert_eq!(all.goals, vec![end]);
+
+        let paths = all.paths();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.len() == 7 && p[0] == start && p[6] == end));
+        // every open tile but the dead end past E
+        assert_eq!(all.states().len(), 11);
+        assert!(all.predecessors(&start).is_empty());
+    }
+}
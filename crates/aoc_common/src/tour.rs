@@ -0,0 +1,141 @@
+use std::ops::Add;
+
+use num_traits::Zero;
+
+/// A route visiting every node exactly once, as found by the Held–Karp functions below.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tour<W> {
+    pub cost: W,
+    /// Node ids in visiting order. A closed tour starts at 0 and doesn't repeat it at the end.
+    pub order: Vec<usize>,
+}
+
+/// The cheapest path through every node, starting and ending anywhere.
+pub fn shortest_path<W>(dist: &[Vec<W>]) -> Tour<W>
+    where W: Copy + Ord + Zero + Add<Output = W>
+{
+    held_karp(dist, false, |a, b| a < b)
+}
+
+/// The most expensive path through every node, starting and ending anywhere.
+pub fn longest_path<W>(dist: &[Vec<W>]) -> Tour<W>
+    where W: Copy + Ord + Zero + Add<Output = W>
+{
+    held_karp(dist, false, |a, b| a > b)
+}
+
+/// The cheapest round trip through every node.
+pub fn shortest_tour<W>(dist: &[Vec<W>]) -> Tour<W>
+    where W: Copy + Ord + Zero + Add<Output = W>
+{
+    held_karp(dist, true, |a, b| a < b)
+}
+
+/// The most expensive round trip through every node.
+pub fn longest_tour<W>(dist: &[Vec<W>]) -> Tour<W>
+    where W: Copy + Ord + Zero + Add<Output = W>
+{
+    held_karp(dist, true, |a, b| a > b)
+}
+
+/// Dynamic programming over subsets: the best cost of a path covering the nodes in `mask` and
+/// ending at `last` only depends on the best paths covering `mask` without `last`. That's
+/// O(2^n n^2) time and O(2^n n) space instead of trying all n! orders, so fine up to about 20
+/// nodes. `dist[a][b]` is the cost of going from a to b and has to be given for every pair.
+fn held_karp<W, F>(dist: &[Vec<W>], closed: bool, better: F) -> Tour<W>
+    where W: Copy + Ord + Zero + Add<Output = W>, F: Fn(W, W) -> bool
+{
+    let n = dist.len();
+    if n <= 1 {
+        return Tour { cost: W::zero(), order: (0..n).collect() };
+    }
+    assert!(n < usize::BITS as usize, "too many nodes for a bitmask: {}", n);
+
+    // closed tours are all rotated to start at node 0, which is only ever first
+    let valid = |mask: usize, last: usize| {
+        mask & (1 << last) != 0 && (!closed || (mask & 1 == 1 && (last != 0 || mask == 1)))
+    };
+    let pick = |a: (usize, W), b: (usize, W)| if better(b.1, a.1) { b } else { a };
+
+    let full = (1 << n) - 1;
+    let mut best = vec![W::zero(); (full + 1) * n];
+    for mask in 1..=full {
+        // a single node is a path of cost 0
+        if mask & (mask - 1) == 0 {
+            continue;
+        }
+        for last in (0..n).filter(|last| valid(mask, *last)) {
+            let rest = mask ^ (1 << last);
+            best[mask * n + last] = (0..n).filter(|prev| valid(rest, *prev))
+                .map(|prev| (prev, best[rest * n + prev] + dist[prev][last]))
+                .reduce(pick).unwrap().1;
+        }
+    }
+
+    let finish = |last: usize| best[full * n + last] + if closed { dist[last][0] } else { W::zero() };
+    let (mut last, cost) = (0..n).filter(|last| valid(full, *last)).map(|last| (last, finish(last))).reduce(pick).unwrap();
+
+    let mut order = vec![last];
+    let mut mask = full;
+    while mask & (mask - 1) != 0 {
+        let rest = mask ^ (1 << last);
+        let prev = (0..n).find(|prev| valid(rest, *prev) && best[rest * n + prev] + dist[*prev][last] == best[mask * n + last]).unwrap();
+        order.push(prev);
+        mask = rest;
+        last = prev;
+    }
+    order.reverse();
+
+    Tour { cost, order }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{longest_path, longest_tour, shortest_path, shortest_tour, Tour};
+    use crate::full_permutations;
+
+    /// Sums the legs of `order`, and the way back to the start for a tour.
+    fn cost(dist: &[Vec<i32>], order: &[usize], closed: bool) -> i32 {
+        let legs: i32 = order.windows(2).map(|w| dist[w[0]][w[1]]).sum();
+        legs + if closed { dist[order[order.len() - 1]][order[0]] } else { 0 }
+    }
+
+    #[test]
+    fn cities() {
+        // London, Dublin and Belfast
+        let dist = vec![vec![0, 464, 518], vec![464, 0, 141], vec![518, 141, 0]];
+        let path = shortest_path(&dist);
+        assert_eq!(path.cost, 605);
+        assert!(path.order == vec![0, 1, 2] || path.order == vec![2, 1, 0]);
+        assert_eq!(longest_path(&dist).cost, 982);
+        assert_eq!(shortest_tour(&dist).cost, 1123);
+
+        assert_eq!(shortest_path::<i32>(&[]), Tour { cost: 0, order: vec![] });
+        assert_eq!(longest_tour(&[vec![5]]), Tour { cost: 0, order: vec![0] });
+    }
+
+    #[test]
+    fn against_permutations() {
+        let n = 7;
+        let dist: Vec<Vec<i32>> = (0..n).map(|a| (0..n).map(|b| if a == b { 0 } else { ((a * 37 + b * 11) % 23) as i32 - 5 }).collect()).collect();
+        let ids: Vec<usize> = (0..n).collect();
+        let orders: Vec<Vec<usize>> = full_permutations(&ids).into_iter().map(|p| p.into_iter().copied().collect()).collect();
+
+        type Finder = fn(&[Vec<i32>]) -> Tour<i32>;
+        let finders: [(bool, bool, Finder); 4] = [
+            (false, true, shortest_path),
+            (false, false, longest_path),
+            (true, true, shortest_tour),
+            (true, false, longest_tour),
+        ];
+        for (closed, min, find) in finders {
+            let tour = find(&dist);
+            assert_eq!(tour.order.len(), n);
+            assert_eq!(cost(&dist, &tour.order, closed), tour.cost);
+
+            let costs = orders.iter().map(|order| cost(&dist, order, closed));
+            let expected = if min { costs.min() } else { costs.max() };
+            assert_eq!(Some(tour.cost), expected);
+        }
+    }
+}
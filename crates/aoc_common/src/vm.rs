@@ -0,0 +1,392 @@
+use std::{collections::{HashSet, VecDeque}, fmt::Debug, rc::Rc, str::FromStr};
+
+use crate::file_string;
+
+/// What happens after an instruction executes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Flow<W> {
+    Next,
+    /// Move relative to the current instruction.
+    Jump(i64),
+    Goto(usize),
+    /// Emit a value and carry on with the next instruction.
+    Output(W),
+    /// Move the next input value into a register and carry on. Without input the machine
+    /// blocks on this instruction, so it shouldn't change anything else before returning this.
+    Receive(usize),
+    Halt,
+}
+
+/// An instruction set. Implement this on an instruction enum and a `Machine` can parse, step
+/// and run programs written in it.
+pub trait Instruction {
+    type Word: Copy + Default;
+
+    /// Size of the register file.
+    const REGISTERS: usize;
+
+    fn execute(&self, regs: &mut [Self::Word]) -> Flow<Self::Word>;
+
+    /// Clock cycles the instruction takes.
+    fn cycles(&self) -> u64 {
+        1
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step<W> {
+    Ok,
+    Output(W),
+    /// Waiting for input, the instruction runs again on the next step.
+    Blocked,
+    Halt,
+}
+
+/// How `run_until_loop` ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Halted,
+    /// The instruction at `ip` was about to run for the second time.
+    Loop { ip: usize },
+}
+
+#[derive(Clone)]
+pub struct Machine<I: Instruction> {
+    program: Rc<Vec<I>>,
+    regs: Vec<I::Word>,
+    input: VecDeque<I::Word>,
+    ip: usize,
+    cycles: u64,
+    steps: u64,
+    executed: Vec<u64>,
+    is_halt: bool,
+}
+
+impl<I: Instruction> Machine<I> {
+    pub fn new(program: Vec<I>) -> Self {
+        Self {
+            regs: vec![I::Word::default(); I::REGISTERS],
+            input: VecDeque::new(),
+            is_halt: program.is_empty(),
+            executed: vec![0; program.len()],
+            program: Rc::new(program),
+            ip: 0,
+            cycles: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn program(&self) -> &[I] {
+        &self.program
+    }
+
+    pub fn regs(&self) -> &[I::Word] {
+        &self.regs
+    }
+
+    pub fn regs_mut(&mut self) -> &mut [I::Word] {
+        &mut self.regs
+    }
+
+    pub fn input(&self) -> &VecDeque<I::Word> {
+        &self.input
+    }
+
+    pub fn add_input(&mut self, val: I::Word) {
+        self.input.push_back(val);
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn set_ip(&mut self, ip: usize) {
+        self.ip = ip;
+        self.is_halt = ip >= self.program.len();
+    }
+
+    /// Clock cycles used so far, as counted by `Instruction::cycles`.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Instructions executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// How many times the instruction at each address ran.
+    pub fn executed(&self) -> &[u64] {
+        &self.executed
+    }
+
+    pub fn is_halt(&self) -> bool {
+        self.is_halt
+    }
+
+    /// Back to the start of the program with cleared registers, input and counters.
+    pub fn reset(&mut self) {
+        self.regs.fill(I::Word::default());
+        self.input.clear();
+        self.set_ip(0);
+        self.cycles = 0;
+        self.steps = 0;
+        self.executed.fill(0);
+    }
+
+    pub fn current(&self) -> Option<&I> {
+        self.program.get(self.ip).filter(|_| !self.is_halt)
+    }
+
+    pub fn step(&mut self) -> Step<I::Word> {
+        let program = self.program.clone();
+        let Some(instruction) = program.get(self.ip).filter(|_| !self.is_halt) else {
+            return Step::Halt;
+        };
+
+        let cycles = instruction.cycles();
+        let flow = instruction.execute(&mut self.regs);
+        if let Flow::Receive(reg) = flow {
+            let Some(val) = self.input.pop_front() else {
+                return Step::Blocked;
+            };
+            self.regs[reg] = val;
+        }
+        self.cycles += cycles;
+        self.steps += 1;
+        self.executed[self.ip] += 1;
+
+        let next = match flow {
+            Flow::Next | Flow::Output(_) | Flow::Receive(_) => Some(self.ip + 1),
+            Flow::Jump(offset) => self.ip.checked_add_signed(offset as isize),
+            Flow::Goto(ip) => Some(ip),
+            Flow::Halt => None,
+        };
+        match next {
+            Some(ip) => self.set_ip(ip),
+            None => self.is_halt = true,
+        }
+
+        match flow {
+            Flow::Output(val) => Step::Output(val),
+            _ if self.is_halt => Step::Halt,
+            _ => Step::Ok,
+        }
+    }
+
+    /// Runs until the program halts or blocks waiting for input.
+    pub fn run(&mut self) {
+        while !matches!(self.step(), Step::Halt | Step::Blocked) { }
+    }
+
+    /// Runs until the program halts or blocks, collecting everything it outputs.
+    pub fn run_outputs(&mut self) -> Vec<I::Word> {
+        let mut outputs = Vec::new();
+        loop {
+            match self.step() {
+                Step::Output(val) => outputs.push(val),
+                Step::Halt | Step::Blocked => return outputs,
+                Step::Ok => (),
+            }
+        }
+    }
+
+    /// The next output, or None if the program halts or blocks first.
+    pub fn run_until_output(&mut self) -> Option<I::Word> {
+        loop {
+            match self.step() {
+                Step::Output(val) => return Some(val),
+                Step::Halt | Step::Blocked => return None,
+                Step::Ok => (),
+            }
+        }
+    }
+
+    /// Runs until the program halts or is about to execute an instruction it already ran. For
+    /// programs like the handheld console, where reaching the same ip twice means it will keep
+    /// looping, this is how to detect an infinite loop. Blocking on input counts as a loop.
+    pub fn run_until_loop(&mut self) -> Termination {
+        let mut visited = HashSet::new();
+        while !self.is_halt {
+            if !visited.insert(self.ip) || matches!(self.step(), Step::Blocked) {
+                return Termination::Loop { ip: self.ip };
+            }
+        }
+        Termination::Halted
+    }
+
+    /// Runs to the end, calling `f` with the cycle number, counting from 1, and the registers
+    /// during every clock cycle. An instruction's writes are only seen after its last cycle.
+    /// Stops early if the program blocks waiting for input.
+    pub fn run_cycles<F: FnMut(u64, &[I::Word])>(&mut self, mut f: F) {
+        while let Some(instruction) = self.current() {
+            let (cycles, start, regs) = (instruction.cycles(), self.cycles, self.regs.clone());
+            if matches!(self.step(), Step::Blocked) {
+                return;
+            }
+            for cycle in 1..=cycles {
+                f(start + cycle, &regs);
+            }
+        }
+    }
+}
+
+impl<I: Instruction + FromStr> Machine<I> {
+    /// Parses one instruction per non-empty line.
+    pub fn parse(source: &str) -> Result<Self, I::Err> {
+        let program = source.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::parse).collect::<Result<Vec<I>, _>>()?;
+        Ok(Self::new(program))
+    }
+
+    pub fn from_file(path: &str) -> Self
+        where I::Err: Debug
+    {
+        Self::parse(&file_string(path)).expect("failed to parse program")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::{Flow, Instruction, Machine, Step, Termination};
+
+    /// The handheld console: an accumulator, `nop`, `acc` and relative `jmp`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Handheld {
+        Nop(i64),
+        Acc(i64),
+        Jmp(i64),
+    }
+
+    impl Instruction for Handheld {
+        type Word = i64;
+        const REGISTERS: usize = 1;
+
+        fn execute(&self, regs: &mut [i64]) -> Flow<i64> {
+            match *self {
+                Handheld::Nop(_) => Flow::Next,
+                Handheld::Acc(n) => {
+                    regs[0] += n;
+                    Flow::Next
+                }
+                Handheld::Jmp(n) => Flow::Jump(n),
+            }
+        }
+    }
+
+    impl FromStr for Handheld {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (op, arg) = s.split_once(' ').ok_or_else(|| format!("invalid instruction '{}'", s))?;
+            let arg = arg.parse().map_err(|_| format!("invalid argument '{}'", arg))?;
+            match op {
+                "nop" => Ok(Handheld::Nop(arg)),
+                "acc" => Ok(Handheld::Acc(arg)),
+                "jmp" => Ok(Handheld::Jmp(arg)),
+                _ => Err(format!("unknown instruction '{}'", op)),
+            }
+        }
+    }
+
+    const BOOT: &str = "
+        nop +0
+        acc +1
+        jmp +4
+        acc +3
+        jmp -3
+        acc -99
+        acc +1
+        jmp -4
+        acc +6";
+
+    #[test]
+    fn loop_detection() {
+        let mut machine: Machine<Handheld> = Machine::parse(BOOT).unwrap();
+        assert_eq!(machine.run_until_loop(), Termination::Loop { ip: 1 });
+        assert_eq!(machine.regs(), &[5]);
+        assert_eq!(machine.steps(), 7);
+
+        // swapping the jmp at 7 for a nop lets it boot
+        let mut program = machine.program().to_vec();
+        program[7] = Handheld::Nop(-4);
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run_until_loop(), Termination::Halted);
+        assert_eq!(machine.regs(), &[8]);
+
+        assert_eq!(Machine::<Handheld>::parse("acc +1\nmul 2").err(), Some("unknown instruction 'mul'".to_string()));
+    }
+
+    /// Two cycle adds and one cycle no-ops on a single register, with `out` to emit it and `in`
+    /// to read it.
+    #[derive(Clone, Copy)]
+    enum Crt {
+        Addx(i32),
+        Noop,
+        Out,
+        In,
+    }
+
+    impl Instruction for Crt {
+        type Word = i32;
+        const REGISTERS: usize = 1;
+
+        fn execute(&self, regs: &mut [i32]) -> Flow<i32> {
+            match *self {
+                Crt::Addx(n) => regs[0] += n,
+                Crt::Noop => (),
+                Crt::Out => return Flow::Output(regs[0]),
+                Crt::In => return Flow::Receive(0),
+            }
+            Flow::Next
+        }
+
+        fn cycles(&self) -> u64 {
+            match self {
+                Crt::Addx(_) => 2,
+                _ => 1,
+            }
+        }
+    }
+
+    #[test]
+    fn cycles() {
+        let program = vec![Crt::Noop, Crt::Addx(3), Crt::Out, Crt::Addx(-5), Crt::Out];
+        let mut machine = Machine::new(program);
+        machine.regs_mut()[0] = 1;
+
+        let mut seen = Vec::new();
+        machine.run_cycles(|cycle, regs| seen.push((cycle, regs[0])));
+        assert_eq!(seen, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4), (6, 4), (7, -1)]);
+        assert_eq!(machine.cycles(), 7);
+        assert!(machine.is_halt());
+
+        machine.reset();
+        machine.regs_mut()[0] = 1;
+        assert_eq!(machine.run_until_output(), Some(4));
+        assert_eq!(machine.step(), Step::Ok);
+        assert_eq!(machine.run_outputs(), vec![-1]);
+        assert_eq!(machine.step(), Step::Halt);
+    }
+
+    #[test]
+    fn input() {
+        let mut machine = Machine::new(vec![Crt::In, Crt::Out, Crt::In, Crt::Addx(1), Crt::Out]);
+        machine.add_input(5);
+        assert_eq!(machine.run_outputs(), vec![5]);
+        assert_eq!(machine.step(), Step::Blocked);
+        assert_eq!((machine.ip(), machine.steps()), (2, 2));
+
+        machine.add_input(7);
+        assert_eq!(machine.run_until_output(), Some(8));
+        assert!(machine.is_halt());
+        assert_eq!(machine.executed(), &[1, 1, 1, 1, 1]);
+
+        let mut seen = Vec::new();
+        machine.reset();
+        machine.run_cycles(|cycle, regs| seen.push((cycle, regs[0])));
+        assert!(seen.is_empty());
+        assert_eq!(machine.executed(), &[0; 5]);
+    }
+}
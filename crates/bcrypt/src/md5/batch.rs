@@ -0,0 +1,453 @@
+use std::{collections::VecDeque, ops::Range};
+
+use super::portable::{self, INIT, K, S};
+
+/// Longest message that still fits in a single padded MD5 block.
+pub const MAX_LANE_MESSAGE: usize = 55;
+
+const SEARCH_CHUNK: u64 = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Scalar,
+    Sse2,
+    Avx2,
+}
+
+impl Backend {
+    pub fn detect() -> Self {
+        if Backend::Avx2.is_supported() {
+            Backend::Avx2
+        } else if Backend::Sse2.is_supported() {
+            Backend::Sse2
+        } else {
+            Backend::Scalar
+        }
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+
+    pub fn lanes(self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            Backend::Sse2 => 4,
+            Backend::Avx2 => 8,
+        }
+    }
+}
+
+/// Hashes many independent short messages per call, one message per SIMD lane.
+/// Messages longer than [`MAX_LANE_MESSAGE`] fall back to the streaming hasher.
+#[derive(Clone, Copy, Debug)]
+pub struct Md5Batch {
+    backend: Backend,
+}
+
+impl Md5Batch {
+    pub fn new() -> Self {
+        Self {
+            backend: Backend::detect(),
+        }
+    }
+
+    pub fn with_backend(backend: Backend) -> Self {
+        assert!(backend.is_supported(), "{:?} is not supported on this cpu", backend);
+        Self { backend }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn lanes(&self) -> usize {
+        self.backend.lanes()
+    }
+
+    pub fn compute<T: AsRef<[u8]>>(&self, messages: &[T]) -> Vec<[u8; 16]> {
+        let mut digests = vec![[0; 16]; messages.len()];
+        let mut blocks = Vec::with_capacity(messages.len());
+        let mut lanes = Vec::with_capacity(messages.len());
+
+        for (i, message) in messages.iter().enumerate() {
+            let message = message.as_ref();
+            if message.len() <= MAX_LANE_MESSAGE {
+                blocks.push(single_block(&[message]));
+                lanes.push(i);
+            } else {
+                let digest = portable::Md5::new().compute(message);
+                digests[i].copy_from_slice(&digest);
+            }
+        }
+
+        let states = self.compress(&blocks);
+        for (lane, state) in lanes.into_iter().zip(states) {
+            digests[lane] = state_to_digest(&state);
+        }
+
+        digests
+    }
+
+    /// Replaces every digest with the md5 of its own lowercase hex form, `rounds` times over.
+    /// This is the key stretching from 2016 day 14.
+    pub fn stretch(&self, digests: &mut [[u8; 16]], rounds: usize) {
+        let mut blocks = Vec::with_capacity(digests.len());
+        for _ in 0..rounds {
+            blocks.clear();
+            blocks.extend(digests.iter().map(|d| single_block(&[&hex(d)])));
+
+            for (digest, state) in digests.iter_mut().zip(self.compress(&blocks)) {
+                *digest = state_to_digest(&state);
+            }
+        }
+    }
+
+    fn compress(&self, blocks: &[[u32; 16]]) -> Vec<[u32; 4]> {
+        let mut states = vec![[0; 4]; blocks.len()];
+        match self.backend {
+            Backend::Scalar => unsafe { compress_all::<u32>(blocks, &mut states) },
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => unsafe { compress_all::<x86::Sse2>(blocks, &mut states) },
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => unsafe { x86::compress_all_avx2(blocks, &mut states) },
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => unreachable!(),
+        }
+        states
+    }
+}
+
+impl Default for Md5Batch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Searches `range` for indices where md5(`salt` + decimal index) starts with `hex_prefix`.
+/// Matches are yielded in index order, along with their digests.
+pub fn search_hex_prefix<T: AsRef<[u8]>>(salt: T, range: Range<u64>, hex_prefix: &str) -> HexPrefixSearch {
+    let salt = salt.as_ref().to_vec();
+    assert!(salt.len() + 20 <= MAX_LANE_MESSAGE, "salt is too long to hash in a single block");
+    assert!(hex_prefix.len() <= 32, "a digest only has 32 hex digits, got a prefix of {}", hex_prefix.len());
+
+    let prefix = hex_prefix.bytes().map(|b| match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        b'A'..=b'F' => b - b'A' + 10,
+        _ => panic!("invalid hex digit '{}' in prefix", b as char),
+    }).collect();
+
+    HexPrefixSearch {
+        batch: Md5Batch::new(),
+        salt,
+        prefix,
+        range,
+        found: VecDeque::new(),
+        blocks: Vec::new(),
+    }
+}
+
+pub struct HexPrefixSearch {
+    batch: Md5Batch,
+    salt: Vec<u8>,
+    prefix: Vec<u8>,
+    range: Range<u64>,
+    found: VecDeque<(u64, [u8; 16])>,
+    blocks: Vec<[u32; 16]>,
+}
+
+impl HexPrefixSearch {
+    fn is_match(&self, digest: &[u8; 16]) -> bool {
+        self.prefix.iter().enumerate().all(|(i, nibble)| {
+            let b = digest[i / 2];
+            let actual = if i % 2 == 0 { b >> 4 } else { b & 0x0F };
+            actual == *nibble
+        })
+    }
+}
+
+impl Iterator for HexPrefixSearch {
+    type Item = (u64, [u8; 16]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.found.is_empty() && !self.range.is_empty() {
+            let start = self.range.start;
+            let end = u64::min(self.range.end, start.saturating_add(SEARCH_CHUNK));
+
+            let mut digits = [0; 20];
+            self.blocks.clear();
+            for i in start..end {
+                self.blocks.push(single_block(&[&self.salt, decimal(i, &mut digits)]));
+            }
+
+            for (i, state) in (start..end).zip(self.batch.compress(&self.blocks)) {
+                let digest = state_to_digest(&state);
+                if self.is_match(&digest) {
+                    self.found.push_back((i, digest));
+                }
+            }
+
+            self.range.start = end;
+        }
+
+        self.found.pop_front()
+    }
+}
+
+fn decimal(mut n: u64, buf: &mut [u8; 20]) -> &[u8] {
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return &buf[i..];
+        }
+    }
+}
+
+fn hex(digest: &[u8; 16]) -> [u8; 32] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut s = [0; 32];
+    for (i, b) in digest.iter().enumerate() {
+        s[i * 2] = DIGITS[(b >> 4) as usize];
+        s[i * 2 + 1] = DIGITS[(b & 0x0F) as usize];
+    }
+    s
+}
+
+fn single_block(parts: &[&[u8]]) -> [u32; 16] {
+    let mut bytes = [0u8; 64];
+    let mut len = 0;
+    for part in parts {
+        bytes[len..len + part.len()].copy_from_slice(part);
+        len += part.len();
+    }
+    bytes[len] = 0x80;
+    bytes[56..].copy_from_slice(&(len as u64 * 8).to_le_bytes());
+
+    let mut words = [0; 16];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+fn state_to_digest(state: &[u32; 4]) -> [u8; 16] {
+    let mut digest = [0; 16];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+/// A vector of `LANES` independent u32s. Methods are unsafe because the simd implementations
+/// may only be called when the cpu supports them.
+trait Lanes: Copy {
+    const LANES: usize;
+
+    unsafe fn load(vals: &[u32]) -> Self;
+    unsafe fn store(self, out: &mut [u32]);
+    unsafe fn splat(val: u32) -> Self;
+    unsafe fn add(self, other: Self) -> Self;
+    unsafe fn and(self, other: Self) -> Self;
+    unsafe fn or(self, other: Self) -> Self;
+    unsafe fn xor(self, other: Self) -> Self;
+    /// !self & other
+    unsafe fn andnot(self, other: Self) -> Self;
+    unsafe fn rotl(self, bits: u32) -> Self;
+}
+
+impl Lanes for u32 {
+    const LANES: usize = 1;
+
+    unsafe fn load(vals: &[u32]) -> Self { vals[0] }
+    unsafe fn store(self, out: &mut [u32]) { out[0] = self; }
+    unsafe fn splat(val: u32) -> Self { val }
+    unsafe fn add(self, other: Self) -> Self { self.wrapping_add(other) }
+    unsafe fn and(self, other: Self) -> Self { self & other }
+    unsafe fn or(self, other: Self) -> Self { self | other }
+    unsafe fn xor(self, other: Self) -> Self { self ^ other }
+    unsafe fn andnot(self, other: Self) -> Self { !self & other }
+    unsafe fn rotl(self, bits: u32) -> Self { self.rotate_left(bits) }
+}
+
+#[inline(always)]
+unsafe fn compress_all<V: Lanes>(blocks: &[[u32; 16]], states: &mut [[u32; 4]]) {
+    let mut gathered = [0u32; 8];
+    for (group, out) in blocks.chunks(V::LANES).zip(states.chunks_mut(V::LANES)) {
+        let mut m = [V::splat(0); 16];
+        for (w, word) in m.iter_mut().enumerate() {
+            for (lane, block) in group.iter().enumerate() {
+                gathered[lane] = block[w];
+            }
+            *word = V::load(&gathered);
+        }
+
+        let state = compress(&m);
+        for (i, word) in state.into_iter().enumerate() {
+            word.store(&mut gathered);
+            for (lane, out) in out.iter_mut().enumerate() {
+                out[i] = gathered[lane];
+            }
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn compress<V: Lanes>(m: &[V; 16]) -> [V; 4] {
+    let init = INIT.map(|w| V::splat(w));
+    let [mut a, mut b, mut c, mut d] = init;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => (b.and(c).or(b.andnot(d)), i),
+            1 => (d.and(b).or(d.andnot(c)), (5 * i + 1) % 16),
+            2 => (b.xor(c).xor(d), (3 * i + 5) % 16),
+            _ => (c.xor(b.or(d.xor(V::splat(!0)))), (7 * i) % 16),
+        };
+
+        let f = f.add(a).add(V::splat(K[i])).add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.add(f.rotl(S[i]));
+    }
+
+    [a.add(init[0]), b.add(init[1]), c.add(init[2]), d.add(init[3])]
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::Lanes;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Sse2(__m128i);
+
+    impl Lanes for Sse2 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        unsafe fn load(vals: &[u32]) -> Self { Sse2(_mm_loadu_si128(vals[..4].as_ptr() as *const __m128i)) }
+        #[inline(always)]
+        unsafe fn store(self, out: &mut [u32]) { _mm_storeu_si128(out[..4].as_mut_ptr() as *mut __m128i, self.0) }
+        #[inline(always)]
+        unsafe fn splat(val: u32) -> Self { Sse2(_mm_set1_epi32(val as i32)) }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self { Sse2(_mm_add_epi32(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self { Sse2(_mm_and_si128(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self { Sse2(_mm_or_si128(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self { Sse2(_mm_xor_si128(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn andnot(self, other: Self) -> Self { Sse2(_mm_andnot_si128(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn rotl(self, bits: u32) -> Self {
+            let left = _mm_sll_epi32(self.0, _mm_cvtsi32_si128(bits as i32));
+            let right = _mm_srl_epi32(self.0, _mm_cvtsi32_si128(32 - bits as i32));
+            Sse2(_mm_or_si128(left, right))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Avx2(__m256i);
+
+    impl Lanes for Avx2 {
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        unsafe fn load(vals: &[u32]) -> Self { Avx2(_mm256_loadu_si256(vals[..8].as_ptr() as *const __m256i)) }
+        #[inline(always)]
+        unsafe fn store(self, out: &mut [u32]) { _mm256_storeu_si256(out[..8].as_mut_ptr() as *mut __m256i, self.0) }
+        #[inline(always)]
+        unsafe fn splat(val: u32) -> Self { Avx2(_mm256_set1_epi32(val as i32)) }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self { Avx2(_mm256_add_epi32(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self { Avx2(_mm256_and_si256(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self { Avx2(_mm256_or_si256(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self { Avx2(_mm256_xor_si256(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn andnot(self, other: Self) -> Self { Avx2(_mm256_andnot_si256(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn rotl(self, bits: u32) -> Self {
+            let left = _mm256_sll_epi32(self.0, _mm_cvtsi32_si128(bits as i32));
+            let right = _mm256_srl_epi32(self.0, _mm_cvtsi32_si128(32 - bits as i32));
+            Avx2(_mm256_or_si256(left, right))
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn compress_all_avx2(blocks: &[[u32; 16]], states: &mut [[u32; 4]]) {
+        super::compress_all::<Avx2>(blocks, states)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{search_hex_prefix, Backend, Md5Batch};
+    use crate::md5::portable::Md5;
+
+    fn backends() -> impl Iterator<Item = Backend> {
+        [Backend::Scalar, Backend::Sse2, Backend::Avx2].into_iter().filter(|b| b.is_supported())
+    }
+
+    #[test]
+    fn matches_streaming() {
+        let md5 = Md5::new();
+        let messages: Vec<String> = (0..37).map(|i| "x".repeat(i * 2)).collect();
+        for backend in backends() {
+            let digests = Md5Batch::with_backend(backend).compute(&messages);
+            for (message, digest) in messages.iter().zip(digests) {
+                assert_eq!(md5.compute(message), digest, "{:?} md5(\"{}\")", backend, message);
+            }
+        }
+    }
+
+    #[test]
+    fn stretch() {
+        let md5 = Md5::new();
+        let mut expected = md5.compute("abc0");
+        for _ in 0..2016 {
+            let hex: String = expected.iter().map(|b| format!("{:02x}", b)).collect();
+            expected = md5.compute(hex);
+        }
+
+        for backend in backends() {
+            let batch = Md5Batch::with_backend(backend);
+            let mut digests = batch.compute(&["abc0"; 5]);
+            batch.stretch(&mut digests, 2016);
+            assert!(digests.iter().all(|d| d[..] == expected[..]), "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn search() {
+        let (idx, digest) = search_hex_prefix("abcdef", 0..u64::MAX, "00000").next().unwrap();
+        assert_eq!(idx, 609043);
+        assert_eq!(&digest[..3], &[0, 0, 0x01]);
+
+        let found: Vec<u64> = search_hex_prefix("pqrstuv", 1_000_000..1_100_000, "00000").map(|(i, _)| i).collect();
+        assert_eq!(found[0], 1048970);
+    }
+
+    #[test]
+    #[should_panic(expected = "32 hex digits")]
+    fn prefix_longer_than_digest() {
+        search_hex_prefix("abc", 0..1, &"0".repeat(33));
+    }
+}
@@ -0,0 +1,106 @@
+pub mod batch;
+pub mod portable;
+#[cfg(windows)]
+mod win32;
+
+#[cfg(not(windows))]
+pub use portable::Md5;
+#[cfg(windows)]
+pub use win32::Md5;
+
+#[cfg(test)]
+mod test{
+    use super::{portable, Md5};
+
+    const RFC_1321_SUITE: [(&str, &str); 7] = [
+        ("", "d41d8cd98f00b204e9800998ecf8427e"),
+        ("a", "0cc175b9c0f1b6a831c399e269772661"),
+        ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+        ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+        ("abcdefghijklmnopqrstuvwxyz", "c3fcd3d76192e4007dfb496cca67e13b"),
+        ("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789", "d174ab98d277d9f5a5611c2c9f419d9f"),
+        ("12345678901234567890123456789012345678901234567890123456789012345678901234567890", "57edf4a22be3c955ac49da2e2107b67a"),
+    ];
+
+    #[test]
+    fn sanity() {
+        let md5 = Md5::new();
+        let result = md5.compute("hello");
+        let s = bytes_to_string(&result);
+        assert_eq!(s, "5d41402abc4b2a76b9719d911017c592");
+    }
+
+    #[test]
+    fn portable_sanity() {
+        let md5 = portable::Md5::new();
+        let result = md5.compute("hello");
+        let s = bytes_to_string(&result);
+        assert_eq!(s, "5d41402abc4b2a76b9719d911017c592");
+    }
+
+    #[test]
+    fn rfc_1321() {
+        let md5 = Md5::new();
+        for (input, expected) in RFC_1321_SUITE {
+            assert_eq!(bytes_to_string(&md5.compute(input)), expected, "md5(\"{}\")", input);
+        }
+    }
+
+    #[test]
+    fn portable_rfc_1321() {
+        let md5 = portable::Md5::new();
+        for (input, expected) in RFC_1321_SUITE {
+            assert_eq!(bytes_to_string(&md5.compute(input)), expected, "md5(\"{}\")", input);
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let md5 = Md5::new();
+        let input = RFC_1321_SUITE[6].0.as_bytes();
+        for split in [0, 1, 55, 56, 63, 64, 65, input.len()] {
+            let mut hasher = Md5::new();
+            hasher.update(&input[..split]);
+            hasher.update(&input[split..]);
+            assert_eq!(hasher.finalize(), md5.compute(input), "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn finalize_resets() {
+        let mut hasher = Md5::new();
+        hasher.update("garbage");
+        hasher.finalize();
+        hasher.update("hello");
+        assert_eq!(bytes_to_string(&hasher.finalize()), "5d41402abc4b2a76b9719d911017c592");
+    }
+
+    #[test]
+    fn cloned_prefix() {
+        let mut salted = Md5::new();
+        salted.update("abc");
+
+        let mut cloned = salted.clone();
+        cloned.update("def");
+        assert_eq!(cloned.finalize(), Md5::new().compute("abcdef"));
+        assert_eq!(salted.compute("xyz"), Md5::new().compute("abcxyz"));
+        assert_eq!(bytes_to_string(&salted.finalize()), "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    fn bytes_to_string(bytes: &[u8]) -> String {
+        fn byte_to_char(b: u8) -> char {
+            (if b < 10 {
+                b + b'0'
+            } else {
+                b - 10 + b'a'
+            }) as char
+        }
+
+        let mut s = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            s.push(byte_to_char(b >> 4));
+            s.push(byte_to_char(b & 0x0F));
+        }
+        s
+    }
+}
@@ -40,54 +40,48 @@ impl Md5 {
         }
     }
 
-    pub fn compute<T: AsRef<[u8]>>(&self, data: T) -> Vec<u8> {
+    pub fn update<T: AsRef<[u8]>>(&mut self, data: T) {
         unsafe {
             BCryptHashData(self.handle, data.as_ref(), 0).ok().unwrap();
+        }
+    }
 
+    pub fn finalize(&mut self) -> Vec<u8> {
+        unsafe {
             let mut result: Vec<u8> = vec![0; self.hash_length];
             BCryptFinishHash(self.handle, &mut result, 0).ok().unwrap();
 
             result
         }
     }
+
+    pub fn compute<T: AsRef<[u8]>>(&self, data: T) -> Vec<u8> {
+        let mut hasher = self.clone();
+        hasher.update(data);
+        hasher.finalize()
+    }
 }
 
-impl Drop for Md5 {
-    fn drop(&mut self) {
+impl Clone for Md5 {
+    fn clone(&self) -> Self {
+        let mut hash_handle: BCRYPT_HASH_HANDLE = Default::default();
+        let mut hash_bytes: Vec<u8> = vec![0; self.hash_bytes.len()];
         unsafe {
-            self.handle.free();
+            BCryptDuplicateHash(self.handle, &mut hash_handle, Some(&mut hash_bytes), 0).ok().unwrap();
+        }
+        Self {
+            handle: hash_handle,
+            hash_bytes,
+            hash_length: self.hash_length
         }
-        self.hash_bytes.clear();
     }
 }
 
-#[cfg(test)]
-mod test{
-    use super::Md5;
-
-    #[test]
-    fn sanity() {
-        let md5 = Md5::new();
-        let result = md5.compute("hello");
-        let s = bytes_to_string(&result);
-        assert_eq!(s, "5d41402abc4b2a76b9719d911017c592");
-    }
-
-    fn bytes_to_string(bytes: &[u8]) -> String {
-        fn byte_to_char(b: u8) -> char {
-            (if b < 10 {
-                b + b'0'
-            } else {
-                b - 10 + b'a'
-            }) as char
-        }
-
-        let mut s = String::with_capacity(bytes.len() * 2);
-        for b in bytes {
-            s.push(byte_to_char(b >> 4));
-            s.push(byte_to_char(b & 0x0F));
+impl Drop for Md5 {
+    fn drop(&mut self) {
+        unsafe {
+            self.handle.free();
         }
-        s
+        self.hash_bytes.clear();
     }
 }
-
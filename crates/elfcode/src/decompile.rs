@@ -0,0 +1,337 @@
+use std::{collections::BTreeSet, fmt::{self, Display}};
+
+use crate::{Instruction, Opcode, Operand, Program};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Cond {
+    lhs: String,
+    op: &'static str,
+    rhs: String,
+}
+
+impl Cond {
+    fn negate(&self) -> Cond {
+        let op = match self.op {
+            ">" => "<=",
+            "<=" => ">",
+            "==" => "!=",
+            _ => "==",
+        };
+        Cond { op, ..self.clone() }
+    }
+}
+
+impl Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.op, self.rhs)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Stmt {
+    Assign(String),
+    /// Jump to an address, or past the end of the program when `target` is None.
+    Goto { cond: Option<Cond>, target: Option<usize> },
+    /// Jump to an address computed at run time.
+    Computed(String),
+}
+
+/// One statement covering the instructions `start..=end`.
+#[derive(Clone, Debug)]
+struct Item {
+    start: usize,
+    end: usize,
+    stmt: Stmt,
+}
+
+struct Line {
+    addr: Option<usize>,
+    depth: usize,
+    text: String,
+}
+
+/// Pseudo-code for an ElfCode program. Writes to the ip register become gotos, comparisons
+/// feeding a relative jump become conditions, backward jumps become `do { } while` loops and
+/// forward conditional jumps become `if` blocks. Whatever doesn't nest is left as a goto to a
+/// label.
+pub struct Listing {
+    lines: Vec<Line>,
+    labels: BTreeSet<usize>,
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines.iter() {
+            let label = match line.addr {
+                Some(addr) if self.labels.contains(&addr) => format!("L{}:", addr),
+                _ => String::new(),
+            };
+            let addr = line.addr.map(|a| a.to_string()).unwrap_or_default();
+            writeln!(f, "{:>4}  {:<6}{}{}", addr, label, "    ".repeat(line.depth), line.text)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn decompile(program: &Program) -> Listing {
+    let items = fold(program, lift(program));
+    let mut listing = Listing { lines: Vec::new(), labels: BTreeSet::new() };
+    render(&items, 0, items.len(), 0, &mut listing);
+    listing
+}
+
+fn reg(r: usize, addr: usize, ip_reg: Option<usize>) -> String {
+    if Some(r) == ip_reg {
+        addr.to_string()
+    } else {
+        format!("r{}", r)
+    }
+}
+
+fn operand(operand: Operand, val: usize, addr: usize, ip_reg: Option<usize>) -> String {
+    match operand {
+        Operand::Reg => reg(val, addr, ip_reg),
+        _ => val.to_string(),
+    }
+}
+
+fn expr(instruction: &Instruction, addr: usize, ip_reg: Option<usize>) -> (String, Option<&'static str>) {
+    let (a_operand, b_operand) = instruction.op.operands();
+    let a = operand(a_operand, instruction.a, addr, ip_reg);
+    let b = operand(b_operand, instruction.b, addr, ip_reg);
+    let op = match instruction.op {
+        Opcode::Addr | Opcode::Addi => "+",
+        Opcode::Mulr | Opcode::Muli => "*",
+        Opcode::Banr | Opcode::Bani => "&",
+        Opcode::Borr | Opcode::Bori => "|",
+        Opcode::Setr | Opcode::Seti => return (a, None),
+        Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr => ">",
+        Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr => "==",
+    };
+    (format!("{} {} {}", a, op, b), Some(op))
+}
+
+/// Numeric value of an expression that only reads constants and the ip register.
+fn constant(instruction: &Instruction, addr: usize, ip_reg: Option<usize>) -> Option<usize> {
+    let (a_operand, b_operand) = instruction.op.operands();
+    let value = |operand, val: usize| match operand {
+        Operand::Reg if Some(val) == ip_reg => Some(addr),
+        Operand::Reg => None,
+        _ => Some(val),
+    };
+    let (a, b) = (value(a_operand, instruction.a)?, value(b_operand, instruction.b)?);
+    let mut regs = [0; 6];
+    regs[0] = a;
+    regs[1] = b;
+    let (a, b) = (if a_operand == Operand::Reg { 0 } else { a }, if b_operand == Operand::Reg { 1 } else { b });
+    instruction.op.apply(&regs, a, b)
+}
+
+fn lift(program: &Program) -> Vec<Item> {
+    let ip_reg = program.ip_reg;
+    let len = program.instructions.len();
+    program.instructions.iter().enumerate().map(|(addr, instruction)| {
+        let stmt = if Some(instruction.c) == ip_reg {
+            match constant(instruction, addr, ip_reg) {
+                Some(val) => Stmt::Goto { cond: None, target: Some(val + 1).filter(|t| *t < len) },
+                None => Stmt::Computed(format!("1 + ({})", expr(instruction, addr, ip_reg).0)),
+            }
+        } else {
+            let target = reg(instruction.c, addr, ip_reg);
+            let (value, op) = expr(instruction, addr, ip_reg);
+            let (a, b) = value.split_once(&format!(" {} ", op.unwrap_or_default())).unwrap_or((&value, ""));
+            match op {
+                Some(op @ ("+" | "*" | "&" | "|")) if a == target => Stmt::Assign(format!("{} {}= {}", target, op, b)),
+                Some(op @ ("+" | "*" | "&" | "|")) if b == target => Stmt::Assign(format!("{} {}= {}", target, op, a)),
+                _ => Stmt::Assign(format!("{} = {}", target, value)),
+            }
+        };
+        Item { start: addr, end: addr, stmt }
+    }).collect()
+}
+
+fn fold(program: &Program, items: Vec<Item>) -> Vec<Item> {
+    let ip_reg = program.ip_reg;
+    let code = &program.instructions;
+
+    // `rT = a > b; ip += rT` skips the next instruction when the comparison holds
+    let mut folded: Vec<Item> = Vec::new();
+    for item in items {
+        let addr = item.start;
+        let instruction = code[addr];
+        let is_relative = instruction.op == Opcode::Addr && Some(instruction.c) == ip_reg
+            && (Some(instruction.a) == ip_reg || Some(instruction.b) == ip_reg);
+        let flag = if Some(instruction.a) == ip_reg { instruction.b } else { instruction.a };
+
+        let compare = folded.last().filter(|_| is_relative).and_then(|prev| {
+            let prev_instruction = code[prev.start];
+            let is_compare = matches!(prev_instruction.op, Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr | Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr);
+            (prev.start == prev.end && is_compare && prev_instruction.c == flag).then_some(prev_instruction)
+        });
+
+        match compare {
+            Some(compare) => {
+                let (a_operand, b_operand) = compare.op.operands();
+                let start = addr - 1;
+                let cond = Cond {
+                    lhs: operand(a_operand, compare.a, start, ip_reg),
+                    op: expr(&compare, start, ip_reg).1.unwrap(),
+                    rhs: operand(b_operand, compare.b, start, ip_reg),
+                };
+                let target = Some(addr + 2).filter(|t| *t < code.len());
+                *folded.last_mut().unwrap() = Item { start, end: addr, stmt: Stmt::Goto { cond: Some(cond), target } };
+            }
+            None => folded.push(item),
+        }
+    }
+
+    // `if c goto +2; goto t` is `if !c goto t`
+    let mut result: Vec<Item> = Vec::new();
+    for item in folded {
+        if let (Some(prev), Stmt::Goto { cond: None, target }) = (result.last_mut(), &item.stmt) {
+            if let Stmt::Goto { cond: Some(cond), target: skip } = &prev.stmt {
+                if *skip == Some(item.end + 1) && prev.end + 1 == item.start {
+                    *prev = Item { start: prev.start, end: item.end, stmt: Stmt::Goto { cond: Some(cond.negate()), target: *target } };
+                    continue;
+                }
+            }
+        }
+        result.push(item);
+    }
+    result
+}
+
+/// True when no jump from outside `items[from..to]` lands inside it, other than at its first
+/// item.
+fn single_entry(items: &[Item], from: usize, to: usize) -> bool {
+    let (first, last) = (items[from].start, items[to - 1].end);
+    items.iter().enumerate().filter(|(i, _)| !(from..to).contains(i)).all(|(_, item)| match item.stmt {
+        Stmt::Goto { target: Some(t), .. } => t <= first || t > last,
+        _ => true,
+    })
+}
+
+fn render(items: &[Item], lo: usize, hi: usize, depth: usize, listing: &mut Listing) {
+    let index_of = |addr: usize| items[lo..hi].iter().position(|item| item.start == addr).map(|i| i + lo);
+
+    let mut i = lo;
+    while i < hi {
+        let item = &items[i];
+
+        // the last backward jump to this item closes the loop
+        let back = (i..hi).rev()
+            .find(|j| matches!(items[*j].stmt, Stmt::Goto { target: Some(t), .. } if t == item.start))
+            .filter(|j| single_entry(items, i, j + 1));
+        if let Some(j) = back {
+            let Stmt::Goto { cond, .. } = &items[j].stmt else { unreachable!() };
+            let (open, close) = match cond {
+                Some(cond) => ("do {".to_string(), format!("}} while {}", cond)),
+                None => ("loop {".to_string(), "}".to_string()),
+            };
+            listing.lines.push(Line { addr: None, depth, text: open });
+            render(items, i, j, depth + 1, listing);
+            listing.lines.push(Line { addr: Some(items[j].start), depth, text: close });
+            i = j + 1;
+            continue;
+        }
+
+        match &item.stmt {
+            Stmt::Assign(text) => listing.lines.push(Line { addr: Some(item.start), depth, text: text.clone() }),
+            Stmt::Computed(text) => listing.lines.push(Line { addr: Some(item.start), depth, text: format!("goto {}", text) }),
+            Stmt::Goto { cond: Some(cond), target: Some(t) } if index_of(*t).is_some_and(|end| end > i + 1 && single_entry(items, i + 1, end)) => {
+                let end = index_of(*t).unwrap();
+                listing.lines.push(Line { addr: Some(item.start), depth, text: format!("if {} {{", cond.negate()) });
+                render(items, i + 1, end, depth + 1, listing);
+                listing.lines.push(Line { addr: None, depth, text: "}".to_string() });
+                i = end;
+                continue;
+            }
+            Stmt::Goto { cond, target } => {
+                let jump = match target {
+                    Some(t) => {
+                        listing.labels.insert(*t);
+                        format!("goto L{}", t)
+                    }
+                    None => "halt".to_string(),
+                };
+                let text = match cond {
+                    Some(cond) => format!("if {} {}", cond, jump),
+                    None => jump,
+                };
+                listing.lines.push(Line { addr: Some(item.start), depth, text });
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decompile;
+
+    #[test]
+    fn sum_of_divisors() {
+        let program = "
+            #ip 1
+            addi 1 16 1
+            seti 1 2 5
+            seti 1 2 2
+            mulr 5 2 3
+            eqrr 3 4 3
+            addr 3 1 1
+            addi 1 1 1
+            addr 5 0 0
+            addi 2 1 2
+            gtrr 2 4 3
+            addr 1 3 1
+            seti 2 8 1
+            addi 5 1 5
+            gtrr 5 4 3
+            addr 3 1 1
+            seti 1 1 1
+            mulr 1 1 1
+            addi 4 2 4
+            seti 0 0 1";
+
+        let listing = decompile(&program.parse().unwrap()).to_string();
+        let expected = "
+   0        goto L17
+   1  L1:   r5 = 1
+            do {
+   2            r2 = 1
+                do {
+   3                r3 = r5 * r2
+   4                if r3 == r4 {
+   7                    r0 += r5
+                    }
+   8                r2 += 1
+   9            } while r2 <= r4
+  12            r5 += 1
+  13        } while r5 <= r4
+  16        halt
+  17  L17:  r4 += 2
+  18        goto L1
+";
+        assert_eq!(listing, expected[1..]);
+    }
+
+    #[test]
+    fn computed_jump() {
+        // ip = r4 + r5 jumps somewhere absolute, it doesn't skip over the next instruction
+        let program = "
+            #ip 3
+            gtrr 1 2 4
+            addr 4 5 3
+            seti 7 0 0
+            seti 99 0 3";
+
+        let listing = decompile(&program.parse().unwrap()).to_string();
+        let expected = "
+   0        r4 = r1 > r2
+   1        goto 1 + (r4 + r5)
+   2        r0 = 7
+   3        halt
+";
+        assert_eq!(listing, expected[1..]);
+    }
+}
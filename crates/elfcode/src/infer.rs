@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use crate::{Instruction, Opcode, Program};
+
+/// Registers before and after one instruction whose opcode is only known by number, as in the
+/// device manual samples:
+///
+/// ```text
+/// Before: [3, 2, 1, 1]
+/// 9 2 1 2
+/// After:  [3, 2, 2, 1]
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sample {
+    pub before: [usize; 4],
+    pub instruction: [usize; 4],
+    pub after: [usize; 4],
+}
+
+impl Sample {
+    /// Opcodes that turn `before` into `after`.
+    pub fn candidates(&self) -> Vec<Opcode> {
+        Opcode::ALL.into_iter().filter(|op| self.matches(*op)).collect()
+    }
+
+    pub fn matches(&self, op: Opcode) -> bool {
+        let [_, a, b, c] = self.instruction;
+        if c >= self.before.len() {
+            return false;
+        }
+
+        let mut regs = self.before;
+        match op.apply(&regs, a, b) {
+            Some(val) => {
+                regs[c] = val;
+                regs == self.after
+            }
+            None => false,
+        }
+    }
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        let [before, instruction, after] = lines[..] else {
+            return Err(format!("expected 3 lines, found {}", lines.len()));
+        };
+
+        let regs = |line: &str, prefix: &str| {
+            let inner = line.strip_prefix(prefix)
+                .and_then(|l| l.trim().strip_prefix('['))
+                .and_then(|l| l.strip_suffix(']'))
+                .ok_or_else(|| format!("expected '{} [a, b, c, d]', found '{}'", prefix, line))?;
+            numbers(inner.split(',').map(str::trim))
+        };
+
+        Ok(Sample {
+            before: regs(before, "Before:")?,
+            instruction: numbers(instruction.split_whitespace())?,
+            after: regs(after, "After:")?,
+        })
+    }
+}
+
+fn numbers<'a>(parts: impl Iterator<Item = &'a str>) -> Result<[usize; 4], String> {
+    let values = parts.map(|p| p.parse::<usize>().map_err(|_| format!("invalid number '{}'", p))).collect::<Result<Vec<_>, _>>()?;
+    values.try_into().map_err(|values: Vec<usize>| format!("expected 4 numbers, found {}", values.len()))
+}
+
+/// Splits a manual into its samples and the numeric program that follows them.
+pub fn parse_manual(input: &str) -> Result<(Vec<Sample>, Vec<[usize; 4]>), String> {
+    let (samples, program) = match input.rfind("After:") {
+        Some(i) => input.split_at(i + input[i..].find('\n').unwrap_or(input.len() - i)),
+        None => ("", input),
+    };
+
+    let samples = samples.split("\n\n").filter(|s| !s.trim().is_empty()).map(str::parse).collect::<Result<Vec<Sample>, _>>()?;
+    let program = program.lines().filter(|l| !l.trim().is_empty()).map(|l| numbers(l.split_whitespace())).collect::<Result<Vec<_>, _>>()?;
+    Ok((samples, program))
+}
+
+/// Works out which opcode each number stands for. Returns None if the samples contradict each
+/// other or leave more than one assignment possible.
+pub fn infer_opcodes(samples: &[Sample]) -> Option<[Opcode; 16]> {
+    let mut candidates = [u16::MAX; 16];
+    for sample in samples {
+        let number = *candidates.get(sample.instruction[0])?;
+        let mask = Opcode::ALL.iter().enumerate()
+            .filter(|(_, op)| sample.matches(**op))
+            .fold(0, |mask, (i, _)| mask | 1 << i);
+        candidates[sample.instruction[0]] = number & mask;
+    }
+
+    let mut solutions = Vec::new();
+    assign(&mut candidates, &mut solutions);
+    match solutions[..] {
+        [solution] => Some(solution.map(|i| Opcode::ALL[i])),
+        _ => None,
+    }
+}
+
+/// Depth first search over the remaining choices, stopping once a second solution shows the
+/// answer is ambiguous.
+fn assign(candidates: &mut [u16; 16], solutions: &mut Vec<[usize; 16]>) {
+    if solutions.len() > 1 {
+        return;
+    }
+
+    let Some(number) = (0..16).filter(|n| candidates[*n].count_ones() > 1).min_by_key(|n| candidates[*n].count_ones()) else {
+        // everything is decided, check no opcode got used twice
+        if candidates.iter().all(|c| *c != 0) && candidates.iter().fold(0, |all, c| all | c) == u16::MAX {
+            solutions.push(candidates.map(|c| c.trailing_zeros() as usize));
+        }
+        return;
+    };
+
+    for op in 0..16 {
+        if candidates[number] & 1 << op == 0 {
+            continue;
+        }
+
+        let mut next = *candidates;
+        for (n, c) in next.iter_mut().enumerate() {
+            *c = if n == number { 1 << op } else { *c & !(1 << op) };
+        }
+        if next.iter().all(|c| *c != 0) {
+            assign(&mut next, solutions);
+        }
+    }
+}
+
+/// Translates a numeric program using an opcode table from `infer_opcodes`.
+pub fn decode(table: &[Opcode; 16], program: &[[usize; 4]]) -> Program {
+    let instructions = program.iter().map(|[n, a, b, c]| Instruction { op: table[*n], a: *a, b: *b, c: *c }).collect();
+    Program { ip_reg: None, instructions }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, infer_opcodes, parse_manual, Sample};
+    use crate::Opcode;
+
+    #[test]
+    fn sample() {
+        let sample: Sample = "Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]".parse().unwrap();
+        assert_eq!(sample.candidates(), vec![Opcode::Addi, Opcode::Mulr, Opcode::Seti]);
+        assert_eq!("Before: [3, 2]\n9 2 1 2\nAfter: [3, 2, 2, 1]".parse::<Sample>().unwrap_err(), "expected 4 numbers, found 2");
+    }
+
+    #[test]
+    fn infer() {
+        let mut table = Opcode::ALL;
+        table.reverse();
+
+        let mut samples = Vec::new();
+        for (n, op) in table.iter().enumerate() {
+            for before in [[3, 2, 1, 1], [0, 5, 7, 2], [9, 1, 4, 4]] {
+                for (a, b, c) in [(0, 1, 2), (2, 3, 0), (1, 1, 3), (3, 0, 1)] {
+                    let mut after = before;
+                    after[c] = op.apply(&before, a, b).unwrap();
+                    samples.push(Sample { before, instruction: [n, a, b, c], after });
+                }
+            }
+        }
+        assert_eq!(infer_opcodes(&samples), Some(table));
+        assert_eq!(infer_opcodes(&samples[..8]), None);
+
+        let manual = samples[..2].iter()
+            .map(|s| format!("Before: {:?}\n{} {} {} {}\nAfter:  {:?}\n\n", s.before, s.instruction[0], s.instruction[1], s.instruction[2], s.instruction[3], s.after))
+            .collect::<String>() + "\n\n0 1 2 3\n15 0 0 1\n";
+        let (parsed, program) = parse_manual(&manual).unwrap();
+        assert_eq!(parsed, samples[..2]);
+        assert_eq!(decode(&table, &program).to_string(), "eqrr 1 2 3\naddr 0 0 1\n");
+    }
+}
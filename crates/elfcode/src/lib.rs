@@ -0,0 +1,369 @@
+use std::{fmt::{self, Display}, rc::Rc, str::FromStr};
+
+use aoc_common::file_string;
+
+pub mod decompile;
+pub mod infer;
+
+use Opcode::*;
+
+/// Number of registers a `Device` has.
+pub const REGISTERS: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+/// How an instruction reads its `a` and `b` operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Reg,
+    Imm,
+    Unused,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 16] = [Addr, Addi, Mulr, Muli, Banr, Bani, Borr, Bori, Setr, Seti, Gtir, Gtri, Gtrr, Eqir, Eqri, Eqrr];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Addr => "addr",
+            Addi => "addi",
+            Mulr => "mulr",
+            Muli => "muli",
+            Banr => "banr",
+            Bani => "bani",
+            Borr => "borr",
+            Bori => "bori",
+            Setr => "setr",
+            Seti => "seti",
+            Gtir => "gtir",
+            Gtri => "gtri",
+            Gtrr => "gtrr",
+            Eqir => "eqir",
+            Eqri => "eqri",
+            Eqrr => "eqrr",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Opcode> {
+        Self::ALL.into_iter().find(|op| op.name() == name)
+    }
+
+    pub fn operands(self) -> (Operand, Operand) {
+        use Operand::*;
+        match self {
+            Addr | Mulr | Banr | Borr | Gtrr | Eqrr => (Reg, Reg),
+            Addi | Muli | Bani | Bori | Gtri | Eqri => (Reg, Imm),
+            Gtir | Eqir => (Imm, Reg),
+            Setr => (Reg, Unused),
+            Seti => (Imm, Unused),
+        }
+    }
+
+    /// The value the instruction writes to register `c`, or None if it reads a register that
+    /// doesn't exist.
+    pub fn apply(self, regs: &[usize], a: usize, b: usize) -> Option<usize> {
+        let (a_operand, b_operand) = self.operands();
+        let read = |operand, val: usize| match operand {
+            Operand::Reg => regs.get(val).copied(),
+            Operand::Imm | Operand::Unused => Some(val),
+        };
+        let (a, b) = (read(a_operand, a)?, read(b_operand, b)?);
+
+        Some(match self {
+            Addr | Addi => a.wrapping_add(b),
+            Mulr | Muli => a.wrapping_mul(b),
+            Banr | Bani => a & b,
+            Borr | Bori => a | b,
+            Setr | Seti => a,
+            Gtir | Gtri | Gtrr => (a > b) as usize,
+            Eqir | Eqri | Eqrr => (a == b) as usize,
+        })
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub op: Opcode,
+    pub a: usize,
+    pub b: usize,
+    pub c: usize,
+}
+
+impl Instruction {
+    pub fn execute(&self, regs: &mut [usize]) {
+        let val = self.op.apply(regs, self.a, self.b).expect("register out of range");
+        regs[self.c] = val;
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.op, self.a, self.b, self.c)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError { }
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [name, a, b, c] = parts[..] else {
+            return Err(format!("expected 'op a b c', found '{}'", s));
+        };
+
+        let op = Opcode::from_name(name).ok_or_else(|| format!("unknown opcode '{}'", name))?;
+        let arg = |s: &str, operand| match s.parse::<usize>() {
+            Ok(reg) if operand == Operand::Reg && reg >= REGISTERS => Err(format!("register {} out of range", reg)),
+            Ok(val) => Ok(val),
+            Err(_) => Err(format!("invalid operand '{}'", s)),
+        };
+        let (a_operand, b_operand) = op.operands();
+        Ok(Instruction { op, a: arg(a, a_operand)?, b: arg(b, b_operand)?, c: arg(c, Operand::Reg)? })
+    }
+}
+
+/// A listing with an optional `#ip n` directive binding the instruction pointer to register n.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    pub ip_reg: Option<usize>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn from_file(path: &str) -> Self {
+        file_string(path).parse().unwrap()
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ip_reg = None;
+        let mut instructions = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let err = |message: String| ParseError { line: i + 1, message };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(reg) = line.strip_prefix("#ip") {
+                let reg = reg.trim();
+                match reg.parse() {
+                    Ok(reg) if reg < REGISTERS => ip_reg = Some(reg),
+                    _ => return Err(err(format!("invalid ip register '{}'", reg))),
+                }
+            } else {
+                instructions.push(line.parse().map_err(err)?);
+            }
+        }
+
+        Ok(Program { ip_reg, instructions })
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(reg) = self.ip_reg {
+            writeln!(f, "#ip {}", reg)?;
+        }
+        for instruction in self.instructions.iter() {
+            writeln!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a `Program` on six registers. When the program binds the instruction pointer, its
+/// register is loaded from the ip before every instruction and written back after.
+#[derive(Clone)]
+pub struct Device {
+    program: Rc<Program>,
+    regs: [usize; REGISTERS],
+    ip: usize,
+    steps: u64,
+}
+
+impl Device {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program: Rc::new(program),
+            regs: [0; REGISTERS],
+            ip: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Self {
+        Self::new(Program::from_file(path))
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn regs(&self) -> &[usize; REGISTERS] {
+        &self.regs
+    }
+
+    pub fn regs_mut(&mut self) -> &mut [usize; REGISTERS] {
+        &mut self.regs
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Instructions executed so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_halt(&self) -> bool {
+        self.ip >= self.program.instructions.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.regs = [0; REGISTERS];
+        self.ip = 0;
+        self.steps = 0;
+    }
+
+    /// The instruction about to execute.
+    pub fn current(&self) -> Option<&Instruction> {
+        self.program.instructions.get(self.ip)
+    }
+
+    /// Executes one instruction, returning false if the program had already halted.
+    pub fn step(&mut self) -> bool {
+        let Some(instruction) = self.program.instructions.get(self.ip) else {
+            return false;
+        };
+
+        if let Some(reg) = self.program.ip_reg {
+            self.regs[reg] = self.ip;
+        }
+        instruction.execute(&mut self.regs);
+        if let Some(reg) = self.program.ip_reg {
+            self.ip = self.regs[reg];
+        }
+
+        self.ip = self.ip.wrapping_add(1);
+        self.steps += 1;
+        true
+    }
+
+    pub fn run(&mut self) {
+        while self.step() { }
+    }
+
+    /// Calls `hook` before every instruction and stops as soon as it returns true. Returns
+    /// false if the program halted first.
+    pub fn run_until<F: FnMut(&Device) -> bool>(&mut self, mut hook: F) -> bool {
+        while !self.is_halt() {
+            if hook(self) {
+                return true;
+            }
+            self.step();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Device, Instruction, Opcode, Program};
+
+    const EXAMPLE: &str = "
+        #ip 0
+        seti 5 0 1
+        seti 6 0 2
+        addi 0 1 0
+        addr 1 2 3
+        setr 1 0 0
+        seti 8 0 4
+        seti 9 0 5";
+
+    #[test]
+    fn opcodes() {
+        let regs = [3, 2, 1, 1];
+        let matching: Vec<Opcode> = Opcode::ALL.into_iter().filter(|op| op.apply(&regs, 2, 1) == Some(2)).collect();
+        assert_eq!(matching, vec![Opcode::Addi, Opcode::Mulr, Opcode::Seti]);
+        assert_eq!(Opcode::Gtir.apply(&regs, 7, 0), Some(1));
+        assert_eq!(Opcode::Eqrr.apply(&regs, 2, 9), None);
+    }
+
+    #[test]
+    fn parse() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        assert_eq!(program.ip_reg, Some(0));
+        assert_eq!(program.instructions[3], Instruction { op: Opcode::Addr, a: 1, b: 2, c: 3 });
+        assert_eq!(program.to_string().parse::<Program>().unwrap(), program);
+
+        let err = "#ip 0\nseti 5 0".parse::<Program>().unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected 'op a b c', found 'seti 5 0'");
+        assert_eq!("nope 1 2 3".parse::<Program>().unwrap_err().message, "unknown opcode 'nope'");
+
+        assert_eq!("seti 0 0 0\naddr 7 0 0".parse::<Program>().unwrap_err().to_string(), "line 2: register 7 out of range");
+        assert_eq!("seti 0 0 9".parse::<Program>().unwrap_err().message, "register 9 out of range");
+        assert_eq!("#ip 6".parse::<Program>().unwrap_err().to_string(), "line 1: invalid ip register '6'");
+        // immediates can be anything
+        assert!("seti 99 7 5".parse::<Program>().is_ok());
+    }
+
+    #[test]
+    fn run() {
+        let mut device = Device::new(EXAMPLE.parse().unwrap());
+        device.run();
+        assert_eq!(device.regs(), &[6, 5, 6, 0, 0, 9]);
+        assert_eq!(device.steps(), 5);
+
+        device.reset();
+        let mut seen = Vec::new();
+        assert!(device.run_until(|device| {
+            seen.push(device.ip());
+            device.current().is_some_and(|i| i.op == Opcode::Setr)
+        }));
+        assert_eq!(seen, vec![0, 1, 2, 4]);
+        assert_eq!(device.regs()[1..3], [5, 6]);
+    }
+}
@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fmt::{self, Display}};
+
+use crate::disasm::{Instruction, Mode, Op, Param};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError { }
+
+enum Statement<'a> {
+    Op(Op, Vec<&'a str>),
+    Data(Vec<&'a str>),
+}
+
+struct Line<'a> {
+    number: usize,
+    addr: usize,
+    statement: Statement<'a>,
+}
+
+/// Assembles the listing syntax produced by `disasm`:
+///
+/// ```text
+/// loop:   in [x]              ; labels end with ':', comments start with ';'
+///         jf [x], #done       ; #imm, [pos] and rb+off operands
+///         out rb-1
+///         jt #1, #loop
+/// done:   hlt
+/// x:      data 0
+/// ```
+///
+/// Anywhere a number is expected a label can be used instead, optionally offset by `+n` or `-n`.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut lines: Vec<Line> = Vec::new();
+    let mut addr = 0;
+
+    for (i, text) in source.lines().enumerate() {
+        let number = i + 1;
+        let err = |message: String| AsmError { line: number, message };
+
+        let mut text = text.split(';').next().unwrap().trim();
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_identifier(label) {
+                return Err(err(format!("invalid label '{}'", label)));
+            }
+            if labels.insert(label, addr).is_some() {
+                return Err(err(format!("duplicate label '{}'", label)));
+            }
+            text = rest.trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let operands: Vec<&str> = if rest.trim().is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(str::trim).collect()
+        };
+
+        let statement = if mnemonic == "data" {
+            if operands.is_empty() {
+                return Err(err("data needs at least one value".to_string()));
+            }
+            Statement::Data(operands)
+        } else {
+            let op = Op::from_mnemonic(mnemonic).ok_or_else(|| err(format!("unknown mnemonic '{}'", mnemonic)))?;
+            if operands.len() != op.param_count() {
+                return Err(err(format!("{} takes {} operands, found {}", op, op.param_count(), operands.len())));
+            }
+            Statement::Op(op, operands)
+        };
+
+        let size = match &statement {
+            Statement::Op(op, _) => 1 + op.param_count(),
+            Statement::Data(values) => values.len(),
+        };
+        lines.push(Line { number, addr, statement });
+        addr += size;
+    }
+
+    let mut code = Vec::with_capacity(addr);
+    for line in lines {
+        let err = |message: String| AsmError { line: line.number, message };
+        debug_assert_eq!(code.len(), line.addr);
+
+        match line.statement {
+            Statement::Op(op, operands) => {
+                let mut params = Vec::with_capacity(operands.len());
+                for (i, operand) in operands.into_iter().enumerate() {
+                    let param = parse_operand(operand, &labels).map_err(err)?;
+                    if param.mode == Mode::Immediate && op.write_param() == Some(i) {
+                        return Err(err(format!("{} can't write to immediate operand '{}'", op, operand)));
+                    }
+                    params.push(param);
+                }
+                code.extend(Instruction { addr: line.addr, op, params }.encode());
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    code.push(parse_value(value, &labels).map_err(err)?);
+                }
+            }
+        }
+    }
+
+    Ok(code)
+}
+
+fn parse_operand(operand: &str, labels: &HashMap<&str, usize>) -> Result<Param, String> {
+    if let Some(value) = operand.strip_prefix('#') {
+        Ok(Param { mode: Mode::Immediate, value: parse_value(value, labels)? })
+    } else if let Some(value) = operand.strip_prefix('[').and_then(|o| o.strip_suffix(']')) {
+        Ok(Param { mode: Mode::Position, value: parse_value(value, labels)? })
+    } else if let Some(offset) = operand.strip_prefix("rb") {
+        let value = match offset.trim() {
+            "" => 0,
+            offset => parse_value(offset, labels)?,
+        };
+        Ok(Param { mode: Mode::Relative, value })
+    } else {
+        Err(format!("invalid operand '{}', expected #imm, [pos] or rb+off", operand))
+    }
+}
+
+fn parse_value(value: &str, labels: &HashMap<&str, usize>) -> Result<i64, String> {
+    let value = value.trim();
+    if let Ok(n) = value.parse::<i64>() {
+        return Ok(n);
+    }
+
+    // labels can be offset with +n or -n, the sign is found after the first character so
+    // plain negative numbers never get here
+    let (label, offset) = match value.char_indices().skip(1).find(|(_, c)| *c == '+' || *c == '-') {
+        Some((i, _)) => {
+            let offset = value[i..].replace(' ', "");
+            let offset = offset.trim_start_matches('+').parse::<i64>().map_err(|_| format!("invalid offset in '{}'", value))?;
+            (value[..i].trim(), offset)
+        }
+        None => (value, 0),
+    };
+
+    match labels.get(label) {
+        Some(addr) => Ok(*addr as i64 + offset),
+        None if is_identifier(label) => Err(format!("undefined label '{}'", label)),
+        None => Err(format!("invalid value '{}'", value)),
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod test {
+    use super::{assemble, AsmError};
+    use crate::disasm::disassemble;
+
+    #[test]
+    fn encodes_modes() {
+        let code = assemble("\
+            add [4], #3, [4]
+            mul rb+1, rb-2, rb
+            hlt").unwrap();
+        assert_eq!(code, vec![1001, 4, 3, 4, 22202, 1, -2, 0, 99]);
+    }
+
+    #[test]
+    fn labels() {
+        let code = assemble("
+            start:  in [x]
+                    jt [x], #start
+                    out [x+1]           ; offset from a label
+            x:      data 7, start, x-2
+                    hlt").unwrap();
+        assert_eq!(code, vec![3, 7, 1005, 7, 0, 4, 8, 7, 0, 5, 99]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(assemble("hlt\nfoo #1"), Err(AsmError { line: 2, message: "unknown mnemonic 'foo'".to_string() }));
+        assert_eq!(assemble("add #1, #2").unwrap_err().message, "add takes 3 operands, found 2");
+        assert_eq!(assemble("in #1").unwrap_err().message, "in can't write to immediate operand '#1'");
+        assert_eq!(assemble("jt #1, #nowhere").unwrap_err().message, "undefined label 'nowhere'");
+        assert_eq!(assemble("out 5").unwrap_err().message, "invalid operand '5', expected #imm, [pos] or rb+off");
+        assert_eq!(assemble("a: hlt\na: hlt").unwrap_err().message, "duplicate label 'a'");
+    }
+
+    #[test]
+    fn round_trip() {
+        let programs: [&[i64]; 3] = [
+            &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+            &[109, 100, 21101, 13, 0, 0, 21101, 42, 0, 1, 1105, 1, 14, 99, 204, 1, 2106, 0, 0],
+            &[3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99],
+        ];
+
+        for program in programs {
+            let listing = disassemble(program).to_string();
+            assert_eq!(assemble(&listing).unwrap(), program, "{}", listing);
+        }
+    }
+}
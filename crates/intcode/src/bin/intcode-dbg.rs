@@ -0,0 +1,25 @@
+use std::io::{self, BufReader};
+
+use intcode::{debugger::Debugger, IntCode};
+
+fn main() {
+    let mut path = None;
+    let mut script = false;
+    let mut ascii = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--script" => script = true,
+            "--ascii" => ascii = true,
+            _ => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path else {
+        eprintln!("usage: intcode-dbg <program> [--script] [--ascii]");
+        std::process::exit(1);
+    };
+
+    let mut debugger = Debugger::new(IntCode::from_file(&path));
+    debugger.set_ascii(ascii);
+    debugger.session(BufReader::new(io::stdin()), io::stdout(), !script).unwrap();
+}
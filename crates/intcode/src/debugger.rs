@@ -0,0 +1,409 @@
+use std::{collections::{BTreeMap, BTreeSet}, io::{self, BufRead, Write}};
+
+use crate::{IntCode, IntCodeAscii, IntCodeError, IntCodeResult};
+
+const HELP: &str = "\
+commands:
+  run | continue | c          run until a breakpoint, watchpoint, input request or halt
+  step | s [n]                execute n instructions (default 1)
+  break | b <addr>            break before executing the instruction at addr
+  break | b op <opcode>       break before executing any instruction with opcode
+  delete | d <addr>           remove the breakpoint at addr
+  delete | d op <opcode>      remove the opcode breakpoint
+  watch | w <addr>            stop after any instruction that changes mem[addr]
+  unwatch <addr>              remove the watchpoint on addr
+  info                        list breakpoints and watchpoints
+  regs                        show pc and relative base
+  mem | x <addr> [len]        dump len (default 8) memory cells starting at addr
+  poke <addr> <val>           write val into mem[addr]
+  input | i <val>...          queue integer input
+  line <text>                 queue text as an ascii line
+  queue | q                   show pending input
+  ascii on|off                print output values as ascii characters
+  help | h                    show this message
+  quit | exit                 leave the debugger";
+
+/// Most cells a single `mem` command prints.
+const MAX_DUMP: usize = 4096;
+/// Highest address `poke` writes to, memory grows to include it so this bounds the allocation.
+const MAX_POKE: usize = 1 << 24;
+
+enum Stop {
+    Breakpoint(usize),
+    OpBreakpoint(i64, usize),
+    Watchpoint(usize, i64, i64),
+    Input,
+    Halt,
+    Error(IntCodeError),
+    Steps,
+}
+
+enum CommandError {
+    Usage(&'static str),
+    Invalid(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for CommandError {
+    fn from(e: io::Error) -> Self {
+        CommandError::Io(e)
+    }
+}
+
+/// Interactive wrapper around an `IntCode` machine. Commands are read line by line from any
+/// `BufRead`, so sessions can be fed from stdin or from a script.
+pub struct Debugger {
+    computer: IntCode,
+    breakpoints: BTreeSet<usize>,
+    op_breakpoints: BTreeSet<i64>,
+    watchpoints: BTreeMap<usize, i64>,
+    started: bool,
+    ascii: bool,
+}
+
+impl Debugger {
+    pub fn new(computer: IntCode) -> Self {
+        Self {
+            computer,
+            breakpoints: BTreeSet::new(),
+            op_breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            started: false,
+            ascii: false,
+        }
+    }
+
+    pub fn computer(&self) -> &IntCode {
+        &self.computer
+    }
+
+    pub fn set_ascii(&mut self, ascii: bool) {
+        self.ascii = ascii;
+    }
+
+    /// Runs commands until `quit` or the end of input. When `prompt` is false nothing but command
+    /// results is written, which keeps scripted transcripts stable.
+    pub fn session<R: BufRead, W: Write>(&mut self, commands: R, mut out: W, prompt: bool) -> io::Result<()> {
+        let mut lines = commands.lines();
+        loop {
+            if prompt {
+                write!(out, "(dbg) ")?;
+                out.flush()?;
+            }
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+
+            if !self.command(line?.trim(), &mut out)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes a single command, returning false when the session should end.
+    pub fn command<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        match self.dispatch(line, out) {
+            Ok(more) => Ok(more),
+            Err(CommandError::Usage(usage)) => writeln!(out, "usage: {}", usage).map(|_| true),
+            Err(CommandError::Invalid(msg)) => writeln!(out, "error: {}", msg).map(|_| true),
+            Err(CommandError::Io(e)) => Err(e),
+        }
+    }
+
+    fn dispatch<W: Write>(&mut self, line: &str, out: &mut W) -> Result<bool, CommandError> {
+        let (cmd, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match cmd {
+            "" => (),
+            "run" | "continue" | "c" => self.resume(None, out)?,
+            "step" | "s" => match args[..] {
+                [] => self.resume(Some(1), out)?,
+                [n] => self.resume(Some(parse(n)?), out)?,
+                _ => return Err(CommandError::Usage("step [n]")),
+            },
+            "break" | "b" => match args[..] {
+                ["op", op] => { self.op_breakpoints.insert(parse(op)?); }
+                [addr] => { self.breakpoints.insert(parse(addr)?); }
+                _ => return Err(CommandError::Usage("break <addr> | break op <opcode>")),
+            },
+            "delete" | "d" => match args[..] {
+                ["op", op] => { self.op_breakpoints.remove(&parse(op)?); }
+                [addr] => { self.breakpoints.remove(&parse(addr)?); }
+                _ => return Err(CommandError::Usage("delete <addr> | delete op <opcode>")),
+            },
+            "watch" | "w" => match args[..] {
+                [addr] => {
+                    let addr = parse(addr)?;
+                    self.watchpoints.insert(addr, self.read(addr));
+                }
+                _ => return Err(CommandError::Usage("watch <addr>")),
+            },
+            "unwatch" => match args[..] {
+                [addr] => { self.watchpoints.remove(&parse(addr)?); }
+                _ => return Err(CommandError::Usage("unwatch <addr>")),
+            },
+            "info" => {
+                writeln!(out, "breakpoints: {:?}", self.breakpoints)?;
+                writeln!(out, "opcode breakpoints: {:?}", self.op_breakpoints)?;
+                writeln!(out, "watchpoints: {:?}", self.watchpoints)?;
+            }
+            "regs" => {
+                writeln!(out, "pc: {}", self.computer.pc())?;
+                writeln!(out, "relative_base: {}", self.computer.relative_base())?;
+                writeln!(out, "halted: {}", self.computer.is_halt())?;
+            }
+            "mem" | "x" => match args[..] {
+                [addr] => self.dump(parse(addr)?, 8, out)?,
+                [addr, len] => self.dump(parse(addr)?, parse(len)?, out)?,
+                _ => return Err(CommandError::Usage("mem <addr> [len]")),
+            },
+            "poke" => match args[..] {
+                [addr, val] => self.poke(parse(addr)?, parse(val)?)?,
+                _ => return Err(CommandError::Usage("poke <addr> <val>")),
+            },
+            "input" | "i" => {
+                let vals = args.iter().map(|a| parse(a)).collect::<Result<Vec<i64>, _>>()?;
+                self.computer.add_input(&vals);
+            }
+            "line" => self.computer.write_line(rest),
+            "queue" | "q" => {
+                let queue = self.computer.input();
+                if queue.is_empty() {
+                    writeln!(out, "input queue: empty")?;
+                } else {
+                    writeln!(out, "input queue: {:?}", queue)?;
+                }
+            }
+            "ascii" => match args[..] {
+                ["on"] => self.ascii = true,
+                ["off"] => self.ascii = false,
+                _ => return Err(CommandError::Usage("ascii on|off")),
+            },
+            "help" | "h" => writeln!(out, "{}", HELP)?,
+            "quit" | "exit" => return Ok(false),
+            _ => return Err(CommandError::Invalid(format!("unknown command '{}', try help", cmd))),
+        }
+
+        Ok(true)
+    }
+
+    fn resume<W: Write>(&mut self, steps: Option<usize>, out: &mut W) -> io::Result<()> {
+        let stop = self.execute(steps, out)?;
+        self.started = true;
+
+        let pc = self.computer.pc();
+        match stop {
+            Stop::Breakpoint(addr) => writeln!(out, "breakpoint at {}", addr),
+            Stop::OpBreakpoint(op, addr) => writeln!(out, "opcode {} breakpoint at {}", op, addr),
+            Stop::Watchpoint(addr, old, new) => writeln!(out, "watchpoint {}: {} -> {} (pc {})", addr, old, new, pc),
+            Stop::Input => writeln!(out, "waiting for input (pc {})", pc),
+            Stop::Halt => writeln!(out, "halted"),
+            Stop::Error(e) => writeln!(out, "error: {}", e),
+            Stop::Steps => writeln!(out, "pc {}", pc),
+        }
+    }
+
+    fn execute<W: Write>(&mut self, steps: Option<usize>, out: &mut W) -> io::Result<Stop> {
+        let mut count = 0;
+        loop {
+            if steps.is_some_and(|steps| count >= steps) {
+                return Ok(Stop::Steps);
+            }
+
+            // the instruction where execution last stopped always runs, otherwise resuming from
+            // a breakpoint would never make progress
+            let pc = self.computer.pc();
+            if (count > 0 || !self.started) && !self.computer.is_halt() {
+                if self.breakpoints.contains(&pc) {
+                    return Ok(Stop::Breakpoint(pc));
+                }
+                let op = self.read(pc) % 100;
+                if self.op_breakpoints.contains(&op) {
+                    return Ok(Stop::OpBreakpoint(op, pc));
+                }
+            }
+
+            match self.computer.try_step() {
+                Ok(Some(IntCodeResult::Output(val))) => self.output(val, out)?,
+                Ok(Some(IntCodeResult::Input)) => return Ok(Stop::Input),
+                Ok(Some(IntCodeResult::Halt)) => return Ok(Stop::Halt),
+                Ok(None) => (),
+                Err(e) => return Ok(Stop::Error(e)),
+            }
+            count += 1;
+
+            for (addr, old) in self.watchpoints.iter_mut() {
+                let new = self.computer.mem().get(*addr).copied().unwrap_or(0);
+                if new != *old {
+                    let stop = Stop::Watchpoint(*addr, *old, new);
+                    *old = new;
+                    return Ok(stop);
+                }
+            }
+        }
+    }
+
+    fn output<W: Write>(&self, val: i64, out: &mut W) -> io::Result<()> {
+        if self.ascii && (0..=0x7F).contains(&val) {
+            write!(out, "{}", val as u8 as char)
+        } else {
+            writeln!(out, "output: {}", val)
+        }
+    }
+
+    fn dump<W: Write>(&self, addr: usize, len: usize, out: &mut W) -> Result<(), CommandError> {
+        if len > MAX_DUMP {
+            return Err(CommandError::Invalid(format!("can't dump more than {} cells", MAX_DUMP)));
+        }
+        let end = addr.checked_add(len).ok_or_else(|| CommandError::Invalid(format!("address {} out of range", addr)))?;
+        for row in (addr..end).step_by(8) {
+            write!(out, "{:>6}:", row)?;
+            for cell in row..usize::min(row.saturating_add(8), end) {
+                write!(out, " {}", self.read(cell))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn poke(&mut self, addr: usize, val: i64) -> Result<(), CommandError> {
+        if addr > MAX_POKE {
+            return Err(CommandError::Invalid(format!("address {} out of range", addr)));
+        }
+        self.computer.mem_mut().write(addr, val);
+        if let Some(old) = self.watchpoints.get_mut(&addr) {
+            *old = val;
+        }
+        Ok(())
+    }
+
+    fn read(&self, addr: usize) -> i64 {
+        self.computer.mem().get(addr).copied().unwrap_or(0)
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> Result<T, CommandError> {
+    s.parse().map_err(|_| CommandError::Invalid(format!("invalid number '{}'", s)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::Debugger;
+    use crate::IntCode;
+
+    fn transcript(code: &str, script: &str) -> String {
+        let mut debugger = Debugger::new(IntCode::from_string(code));
+        let mut out = Vec::new();
+        debugger.session(script.as_bytes(), &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn breakpoints_and_regs() {
+        // outputs 1 if the input equals 8, 0 otherwise
+        let out = transcript("3,9,8,9,10,9,4,9,99,-1,8", "\
+            break 2\n\
+            run\n\
+            input 8\n\
+            queue\n\
+            run\n\
+            regs\n\
+            mem 9 2\n\
+            run\n\
+            regs\n");
+
+        assert_eq!(out, "\
+waiting for input (pc 0)
+input queue: [8]
+breakpoint at 2
+pc: 2
+relative_base: 0
+halted: false
+     9: 8 8
+output: 1
+halted
+pc: 9
+relative_base: 0
+halted: true
+");
+    }
+
+    #[test]
+    fn step_and_watch() {
+        let out = transcript("1101,2,3,9,1002,9,4,9,99,0", "\
+            b op 2\n\
+            c\n\
+            watch 9\n\
+            s\n\
+            unwatch 9\n\
+            info\n\
+            s\n\
+            s\n\
+            mem x\n\
+            bogus\n");
+
+        assert_eq!(out, "\
+opcode 2 breakpoint at 4
+watchpoint 9: 5 -> 20 (pc 8)
+breakpoints: {}
+opcode breakpoints: {2}
+watchpoints: {}
+halted
+halted
+error: invalid number 'x'
+error: unknown command 'bogus', try help
+");
+    }
+
+    #[test]
+    fn poke_past_end() {
+        let out = transcript("1101,2,3,5,99", "\
+            watch 30\n\
+            poke 30 7\n\
+            mem 30 1\n\
+            s\n\
+            info\n");
+
+        assert_eq!(out, "    30: 7
+pc 4
+breakpoints: {}
+opcode breakpoints: {}
+watchpoints: {30: 7}
+");
+    }
+
+    #[test]
+    fn huge_addresses() {
+        let out = transcript("99", "\
+            mem 18446744073709551615 8\n\
+            mem 18446744073709551615 1\n\
+            mem 0 100000\n\
+            poke 18446744073709551615 1\n\
+            regs\n");
+
+        assert_eq!(out, "\
+error: address 18446744073709551615 out of range
+error: address 18446744073709551615 out of range
+error: can't dump more than 4096 cells
+error: address 18446744073709551615 out of range
+pc: 0
+relative_base: 0
+halted: false
+");
+    }
+
+    #[test]
+    fn reports_errors() {
+        let out = transcript("1101,1,1,5,42", "run\nregs\nrun\n");
+        assert_eq!(out, "\
+error: unknown opcode 42 at pc 4
+pc: 4
+relative_base: 0
+halted: false
+error: unknown opcode 42 at pc 4
+");
+    }
+}
@@ -0,0 +1,457 @@
+use std::{collections::{BTreeMap, BTreeSet}, fmt::{self, Display, Write}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Mul,
+    In,
+    Out,
+    Jt,
+    Jf,
+    Lt,
+    Eq,
+    Arb,
+    Hlt,
+}
+
+impl Op {
+    pub const ALL: [Op; 10] = [Op::Add, Op::Mul, Op::In, Op::Out, Op::Jt, Op::Jf, Op::Lt, Op::Eq, Op::Arb, Op::Hlt];
+
+    pub fn from_code(code: i64) -> Option<Op> {
+        Some(match code {
+            1 => Op::Add,
+            2 => Op::Mul,
+            3 => Op::In,
+            4 => Op::Out,
+            5 => Op::Jt,
+            6 => Op::Jf,
+            7 => Op::Lt,
+            8 => Op::Eq,
+            9 => Op::Arb,
+            99 => Op::Hlt,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            Op::Add => 1,
+            Op::Mul => 2,
+            Op::In => 3,
+            Op::Out => 4,
+            Op::Jt => 5,
+            Op::Jf => 6,
+            Op::Lt => 7,
+            Op::Eq => 8,
+            Op::Arb => 9,
+            Op::Hlt => 99,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Mul => "mul",
+            Op::In => "in",
+            Op::Out => "out",
+            Op::Jt => "jt",
+            Op::Jf => "jf",
+            Op::Lt => "lt",
+            Op::Eq => "eq",
+            Op::Arb => "arb",
+            Op::Hlt => "hlt",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Op> {
+        Op::ALL.into_iter().find(|op| op.mnemonic() == mnemonic)
+    }
+
+    pub fn param_count(self) -> usize {
+        match self {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq => 3,
+            Op::Jt | Op::Jf => 2,
+            Op::In | Op::Out | Op::Arb => 1,
+            Op::Hlt => 0,
+        }
+    }
+
+    /// Index of the parameter this op writes through, if any.
+    pub fn write_param(self) -> Option<usize> {
+        match self {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq => Some(2),
+            Op::In => Some(0),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    pub fn from_code(code: i64) -> Option<Mode> {
+        match code {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Param {
+    pub mode: Mode,
+    pub value: i64,
+}
+
+impl Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            Mode::Position => write!(f, "[{}]", self.value),
+            Mode::Immediate => write!(f, "#{}", self.value),
+            Mode::Relative if self.value < 0 => write!(f, "rb{}", self.value),
+            Mode::Relative => write!(f, "rb+{}", self.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub addr: usize,
+    pub op: Op,
+    pub params: Vec<Param>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `addr`. Fails on unknown opcodes and modes, immediate mode
+    /// writes, stray mode digits and instructions running off the end of memory, which is
+    /// what lets the disassembler tell code from data.
+    pub fn decode(mem: &[i64], addr: usize) -> Option<Instruction> {
+        let word = *mem.get(addr)?;
+        if word < 0 {
+            return None;
+        }
+
+        let op = Op::from_code(word % 100)?;
+        let mut modes = word / 100;
+        let mut params = Vec::with_capacity(op.param_count());
+        for i in 0..op.param_count() {
+            let mode = Mode::from_code(modes % 10)?;
+            if mode == Mode::Immediate && op.write_param() == Some(i) {
+                return None;
+            }
+            params.push(Param { mode, value: *mem.get(addr + 1 + i)? });
+            modes /= 10;
+        }
+
+        if modes != 0 {
+            return None;
+        }
+
+        Some(Instruction { addr, op, params })
+    }
+
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    pub fn encode(&self) -> Vec<i64> {
+        let mut word = self.op.code();
+        let mut scale = 100;
+        for param in self.params.iter() {
+            word += param.mode.code() * scale;
+            scale *= 10;
+        }
+
+        let mut encoded = vec![word];
+        encoded.extend(self.params.iter().map(|p| p.value));
+        encoded
+    }
+
+    /// Addresses execution can continue at, or None when a target can't be known statically.
+    fn successors(&self, mem: &[i64]) -> Vec<Option<usize>> {
+        let next = Some(self.addr + self.size());
+        match self.op {
+            Op::Hlt => vec![],
+            Op::Jt | Op::Jf => {
+                let target = match self.params[1].mode {
+                    Mode::Immediate => to_addr(self.params[1].value),
+                    // position mode targets are read from memory, assume the cell isn't rewritten
+                    Mode::Position => to_addr(self.params[1].value).and_then(|a| mem.get(a)).and_then(|v| to_addr(*v)),
+                    Mode::Relative => None,
+                };
+
+                match self.params[0].mode {
+                    Mode::Immediate if (self.params[0].value != 0) == (self.op == Op::Jt) => vec![target],
+                    Mode::Immediate => vec![next],
+                    _ => vec![next, target],
+                }
+            }
+            _ => vec![next],
+        }
+    }
+
+    /// Immediate values pushed onto the stack frame by the usual `add #ret, #0, rb+n` (or
+    /// `mul #ret, #1, rb+n`) call sequence, which are most likely return addresses. Returns the
+    /// index of the parameter holding the address along with the address.
+    fn return_address(&self) -> Option<(usize, usize)> {
+        let identity = match self.op {
+            Op::Add => 0,
+            Op::Mul => 1,
+            _ => return None,
+        };
+        if self.params[2].mode != Mode::Relative || self.params[..2].iter().any(|p| p.mode != Mode::Immediate) {
+            return None;
+        }
+
+        let (i, value) = match (self.params[0].value, self.params[1].value) {
+            (value, other) if other == identity => (0, value),
+            (other, value) if other == identity => (1, value),
+            _ => return None,
+        };
+        to_addr(value).filter(|addr| *addr > 0).map(|addr| (i, addr))
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+        for (i, param) in self.params.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { ", " }, param)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Code(Instruction),
+    Data { addr: usize, values: Vec<i64> },
+}
+
+impl Item {
+    pub fn addr(&self) -> usize {
+        match self {
+            Item::Code(instruction) => instruction.addr,
+            Item::Data { addr, .. } => *addr,
+        }
+    }
+}
+
+/// A program split into code and data. Displays as a listing that `asm::assemble` reads back.
+#[derive(Clone, Debug)]
+pub struct Disassembly {
+    pub items: Vec<Item>,
+    pub labels: BTreeMap<usize, String>,
+}
+
+impl Disassembly {
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Code(instruction) => Some(instruction),
+            _ => None,
+        })
+    }
+
+    fn operand(&self, instruction: &Instruction, i: usize) -> String {
+        let param = instruction.params[i];
+        let is_target = match instruction.op {
+            Op::Jt | Op::Jf => i == 1,
+            _ => instruction.return_address().is_some_and(|(ret, _)| ret == i),
+        };
+
+        match (is_target, param.mode, to_addr(param.value).and_then(|a| self.labels.get(&a))) {
+            (true, Mode::Immediate, Some(label)) => format!("#{}", label),
+            _ => param.to_string(),
+        }
+    }
+}
+
+impl Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in self.items.iter() {
+            let mut line = String::new();
+            match self.labels.get(&item.addr()) {
+                Some(label) => write!(line, "{:<8}", format!("{}:", label))?,
+                None => write!(line, "{:8}", "")?,
+            }
+
+            match item {
+                Item::Code(instruction) => {
+                    write!(line, "{}", instruction.op)?;
+                    for i in 0..instruction.params.len() {
+                        write!(line, "{}{}", if i == 0 { " " } else { ", " }, self.operand(instruction, i))?;
+                    }
+                }
+                Item::Data { values, .. } => {
+                    write!(line, "data {}", values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))?;
+                }
+            }
+
+            writeln!(f, "{:<40}; {}", line, item.addr())?;
+        }
+        Ok(())
+    }
+}
+
+const DATA_PER_LINE: usize = 8;
+
+/// Splits a program into code and data by following control flow from address 0. Jumps through
+/// relative mode can't be followed, so immediate values pushed by call sequences are also tried
+/// as entry points.
+pub fn disassemble(code: &[i64]) -> Disassembly {
+    let mut owner: Vec<Option<usize>> = vec![None; code.len()];
+    let mut instructions: BTreeMap<usize, Instruction> = BTreeMap::new();
+    let mut targets: BTreeSet<usize> = BTreeSet::new();
+    let mut to_visit: Vec<usize> = vec![0];
+    let mut candidates: Vec<usize> = Vec::new();
+
+    loop {
+        let Some(addr) = to_visit.pop() else {
+            // only fall back to guessed entry points once everything certain is claimed
+            match candidates.pop() {
+                Some(addr) => {
+                    if owner.get(addr).is_some_and(|o| o.is_none()) && Instruction::decode(code, addr).is_some() {
+                        targets.insert(addr);
+                        to_visit.push(addr);
+                    }
+                    continue;
+                }
+                None => break,
+            }
+        };
+
+        if owner.get(addr).is_none_or(|o| o.is_some()) {
+            continue;
+        }
+
+        let Some(instruction) = Instruction::decode(code, addr) else {
+            continue;
+        };
+        if owner[addr..addr + instruction.size()].iter().any(|o| o.is_some()) {
+            continue;
+        }
+        owner[addr..addr + instruction.size()].fill(Some(addr));
+
+        for next in instruction.successors(code).into_iter().flatten() {
+            if matches!(instruction.op, Op::Jt | Op::Jf) && next != addr + instruction.size() {
+                targets.insert(next);
+            }
+            to_visit.push(next);
+        }
+        if let Some((_, ret)) = instruction.return_address() {
+            candidates.push(ret);
+        }
+
+        instructions.insert(addr, instruction);
+    }
+
+    let mut items = Vec::new();
+    let mut addr = 0;
+    while addr < code.len() {
+        if let Some(instruction) = instructions.remove(&addr) {
+            addr += instruction.size();
+            items.push(Item::Code(instruction));
+        } else {
+            let start = addr;
+            while addr < code.len() && owner[addr].is_none() && addr - start < DATA_PER_LINE && (addr == start || !targets.contains(&addr)) {
+                addr += 1;
+            }
+            items.push(Item::Data { addr: start, values: code[start..addr].to_vec() });
+        }
+    }
+
+    let labels = targets.into_iter()
+        .filter(|t| items.iter().any(|item| item.addr() == *t))
+        .map(|t| (t, format!("L{}", t)))
+        .collect();
+
+    Disassembly { items, labels }
+}
+
+fn to_addr(val: i64) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{disassemble, Instruction, Mode, Op, Param};
+
+    #[test]
+    fn decode_modes() {
+        let instruction = Instruction::decode(&[21102, 3, -4, 7], 0).unwrap();
+        assert_eq!(instruction.op, Op::Mul);
+        assert_eq!(instruction.params, vec![
+            Param { mode: Mode::Immediate, value: 3 },
+            Param { mode: Mode::Immediate, value: -4 },
+            Param { mode: Mode::Relative, value: 7 },
+        ]);
+        assert_eq!(instruction.to_string(), "mul #3, #-4, rb+7");
+        assert_eq!(instruction.encode(), vec![21102, 3, -4, 7]);
+
+        assert!(Instruction::decode(&[11101, 1, 1, 1], 0).is_none());
+        assert!(Instruction::decode(&[104], 0).is_none());
+        assert!(Instruction::decode(&[399, 0], 0).is_none());
+        assert!(Instruction::decode(&[42], 0).is_none());
+    }
+
+    #[test]
+    fn listing() {
+        let listing = disassemble(&[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9]).to_string();
+        let expected = "
+        in [12]                         ; 0
+        jf [12], [15]                   ; 2
+        add [13], [14], [13]            ; 5
+L9:     out [13]                        ; 9
+        hlt                             ; 11
+        data -1, 0, 1, 9                ; 12
+";
+        assert_eq!(listing, expected[1..]);
+    }
+
+    #[test]
+    fn calls() {
+        // main calls a function that outputs its argument, using the usual rb based convention
+        let code = [
+            109, 100,           // arb #100
+            21101, 13, 0, 0,    // add #13, #0, rb+0    push the return address
+            21101, 42, 0, 1,    // add #42, #0, rb+1    push the argument
+            1105, 1, 14,        // jt #1, #14           call
+            99,                 // only reachable by returning
+            204, 1,             // out rb+1
+            2106, 0, 0,         // jf #0, rb+0          return
+        ];
+
+        let listing = disassemble(&code).to_string();
+        let expected = "
+        arb #100                        ; 0
+        add #L13, #0, rb+0              ; 2
+        add #42, #0, rb+1               ; 6
+        jt #1, #L14                     ; 10
+L13:    hlt                             ; 13
+L14:    out rb+1                        ; 14
+        jf #0, rb+0                     ; 16
+";
+        assert_eq!(listing, expected[1..]);
+    }
+}
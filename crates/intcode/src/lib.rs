@@ -1,6 +1,16 @@
-use std::{collections::VecDeque, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, fmt::{self, Display}, hash::{Hash, Hasher}, rc::Rc};
 
 use aoc_common::{file_string};
+use disasm::{Instruction, Mode, Op, Param};
+use memory::{Memory, StableHasher};
+use trace::{TraceEvent, Tracer};
+
+pub mod asm;
+pub mod debugger;
+pub mod disasm;
+pub mod memory;
+pub mod network;
+pub mod trace;
 
 #[derive(Debug)]
 pub enum IntCodeResult {
@@ -18,14 +28,101 @@ impl IntCodeResult {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntCodeError {
+    UnknownOpcode { pc: usize, opcode: i64 },
+    InvalidMode { pc: usize, instruction: i64 },
+    ImmediateWrite { pc: usize, instruction: i64 },
+    NegativeAddress { pc: usize, address: i64 },
+    Overflow { pc: usize, a: i64, b: i64 },
+}
+
+impl IntCodeError {
+    pub fn pc(&self) -> usize {
+        match *self {
+            IntCodeError::UnknownOpcode { pc, .. } => pc,
+            IntCodeError::InvalidMode { pc, .. } => pc,
+            IntCodeError::ImmediateWrite { pc, .. } => pc,
+            IntCodeError::NegativeAddress { pc, .. } => pc,
+            IntCodeError::Overflow { pc, .. } => pc,
+        }
+    }
+}
+
+impl Display for IntCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntCodeError::UnknownOpcode { pc, opcode } => write!(f, "unknown opcode {} at pc {}", opcode, pc),
+            IntCodeError::InvalidMode { pc, instruction } => write!(f, "invalid parameter mode in {} at pc {}", instruction, pc),
+            IntCodeError::ImmediateWrite { pc, instruction } => write!(f, "write to immediate parameter in {} at pc {}", instruction, pc),
+            IntCodeError::NegativeAddress { pc, address } => write!(f, "negative address {} at pc {}", address, pc),
+            IntCodeError::Overflow { pc, a, b } => write!(f, "overflow computing with {} and {} at pc {}", a, b, pc),
+        }
+    }
+}
+
+impl std::error::Error for IntCodeError { }
+
+/// What went wrong inside `execute_at`, turned into an `IntCodeError` once pc is restored.
+enum Fault {
+    UnknownOpcode,
+    InvalidMode,
+    ImmediateWrite,
+    NegativeAddress(i64),
+    Overflow(i64, i64),
+}
+
+fn to_addr(val: i64) -> Result<usize, Fault> {
+    usize::try_from(val).map_err(|_| Fault::NegativeAddress(val))
+}
+
+fn add(a: i64, b: i64) -> Result<i64, Fault> {
+    a.checked_add(b).ok_or(Fault::Overflow(a, b))
+}
+
+/// Machine state captured by `IntCode::snapshot`. Memory pages stay shared with the machine
+/// until one of them writes to a page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pc: usize,
+    relative_base: i64,
+    mem: Memory,
+    is_halt: bool,
+    input: VecDeque<i64>,
+}
+
+impl Snapshot {
+    /// Hash of the full machine state that doesn't change between runs or platforms.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Hash for Snapshot {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pc.hash(state);
+        self.relative_base.hash(state);
+        self.mem.hash(state);
+        self.is_halt.hash(state);
+        // one value at a time, hashing the queue as a whole feeds in native endian bytes
+        state.write_usize(self.input.len());
+        for val in self.input.iter() {
+            state.write_i64(*val);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct IntCode {
     pc: usize,
     relative_base: i64,
-    code: Rc<Vec<i64>>,
-    mem: Vec<i64>,
+    code: Memory,
+    mem: Memory,
     is_halt: bool,
-    input: VecDeque<i64>
+    input: VecDeque<i64>,
+    tracer: Option<Rc<RefCell<dyn Tracer>>>,
 }
 
 impl IntCode {
@@ -33,15 +130,16 @@ impl IntCode {
         IntCode {
             pc: 0,
             relative_base: 0,
-            code: Rc::new(code.clone()),
-            mem: code,
+            code: Memory::from_slice(&code),
+            mem: Memory::from_slice(&code),
             is_halt: false,
             input: VecDeque::new(),
+            tracer: None,
         }
     }
 
     pub fn from_string(code: &str) -> Self {
-        Self::new(code.split(',').map(|s| s.parse::<i64>().unwrap()).collect())
+        Self::new(code.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect())
     }
 
     pub fn from_file(path: &str) -> Self {
@@ -52,19 +150,66 @@ impl IntCode {
         self.is_halt
     }
 
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn input(&self) -> &VecDeque<i64> {
+        &self.input
+    }
+
+    /// Calls `tracer` after every executed instruction. Clones of this machine share the tracer.
+    pub fn set_tracer(&mut self, tracer: Rc<RefCell<dyn Tracer>>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn clear_tracer(&mut self) {
+        self.tracer = None;
+    }
+
     pub fn reset(&mut self) {
         self.pc = 0;
         self.relative_base = 0;
         self.is_halt = false;
+        self.mem = self.code.clone();
+    }
 
-        self.mem[..self.code.len()].copy_from_slice(&self.code);
-        self.mem[self.code.len()..].fill(0);
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pc: self.pc,
+            relative_base: self.relative_base,
+            mem: self.mem.clone(),
+            is_halt: self.is_halt,
+            input: self.input.clone(),
+        }
+    }
+
+    /// Returns to a snapshot taken from this or any other machine, keeping the tracer.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.pc = snapshot.pc;
+        self.relative_base = snapshot.relative_base;
+        self.mem = snapshot.mem.clone();
+        self.is_halt = snapshot.is_halt;
+        self.input = snapshot.input.clone();
+    }
+
+    /// Equal for machines that will behave identically from here on, see `Snapshot::state_hash`.
+    pub fn state_hash(&self) -> u64 {
+        self.snapshot().state_hash()
     }
 
     pub fn run(&mut self) -> IntCodeResult {
+        self.try_run().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_run(&mut self) -> Result<IntCodeResult, IntCodeError> {
         loop {
-            if let Some(result) = self.step() {
-                return result;
+            if let Some(result) = self.try_step()? {
+                return Ok(result);
             }
         }
     }
@@ -91,57 +236,133 @@ impl IntCode {
     }
 
     pub fn step(&mut self) -> Option<IntCodeResult> {
+        self.try_step().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Executes a single instruction. On error pc is left on the offending instruction and
+    /// nothing but possibly the size of memory has changed.
+    pub fn try_step(&mut self) -> Result<Option<IntCodeResult>, IntCodeError> {
+        let Some(tracer) = self.tracer.clone() else {
+            return self.execute();
+        };
+
+        let event = if self.is_halt { None } else { self.trace_event() };
+        let result = self.execute()?;
+        if let Some(mut event) = event {
+            // an input instruction without input didn't execute, it runs again once input arrives
+            if !matches!(result, Some(IntCodeResult::Input)) {
+                if let Some((addr, _)) = event.write {
+                    event.write = Some((addr, self.mem.read(addr)));
+                }
+                tracer.borrow_mut().on_step(&event);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Decodes the instruction at pc along with the operand values it's about to read and the
+    /// address it will write to, without touching memory.
+    fn trace_event(&self) -> Option<TraceEvent> {
+        let word = self.peek(self.pc);
+        let op = Op::from_code(word % 100)?;
+        let mut modes = word / 100;
+        let mut instruction = Instruction { addr: self.pc, op, params: Vec::with_capacity(op.param_count()) };
+        let mut values = Vec::new();
+        let mut write = None;
+
+        for i in 0..op.param_count() {
+            let param = Param { mode: Mode::from_code(modes % 10)?, value: self.peek(self.pc + 1 + i) };
+            modes /= 10;
+
+            let addr = match param.mode {
+                Mode::Position => param.value,
+                Mode::Immediate => -1,
+                Mode::Relative => self.relative_base.checked_add(param.value)?,
+            };
+            if op.write_param() == Some(i) {
+                write = Some((usize::try_from(addr).ok()?, 0));
+            } else if param.mode == Mode::Immediate {
+                values.push(param.value);
+            } else {
+                values.push(self.peek(usize::try_from(addr).ok()?));
+            }
+            instruction.params.push(param);
+        }
+
+        Some(TraceEvent { instruction, relative_base: self.relative_base, values, write })
+    }
+
+    fn peek(&self, addr: usize) -> i64 {
+        self.mem.read(addr)
+    }
+
+    fn execute(&mut self) -> Result<Option<IntCodeResult>, IntCodeError> {
         if self.is_halt {
-            return Some(IntCodeResult::Halt);
+            return Ok(Some(IntCodeResult::Halt));
         }
 
+        let pc = self.pc;
+        self.execute_at(pc).map_err(|fault| {
+            self.pc = pc;
+            let word = self.mem.read(pc);
+            match fault {
+                Fault::UnknownOpcode => IntCodeError::UnknownOpcode { pc, opcode: word % 100 },
+                Fault::InvalidMode => IntCodeError::InvalidMode { pc, instruction: word },
+                Fault::ImmediateWrite => IntCodeError::ImmediateWrite { pc, instruction: word },
+                Fault::NegativeAddress(address) => IntCodeError::NegativeAddress { pc, address },
+                Fault::Overflow(a, b) => IntCodeError::Overflow { pc, a, b },
+            }
+        })
+    }
+
+    fn execute_at(&mut self, pc: usize) -> Result<Option<IntCodeResult>, Fault> {
         let (op, mode0, mode1, mode2) = self.decode();
 
-        if op == 99 {
-            self.is_halt = true;
-            return Some(IntCodeResult::Halt);
-        } else { 
-            match op {
-                3 => {
-                    if let Some(val) = self.input.pop_front() {
-                        *self.read_mode_mut(mode0) = val;
-                    }
-                    else {
-                        self.pc -=1;
-                        return Some(IntCodeResult::Input);
-                    }
-                }
-                4 => {
-                    let val = self.read_mode(mode0);
-                    return Some(IntCodeResult::Output(val));
-                }
-                5 | 6 => {
-                    let cond = self.read_mode(mode0);
-                    let new_pc = self.read_mode(mode1);
-                    if (cond != 0 && op == 5) || (cond == 0 && op == 6) {
-                        self.pc = new_pc as usize;
-                    }
-                }
-                9 => {
-                    self.relative_base += self.read_mode(mode0);
+        match op {
+            1 | 2 | 7 | 8 => {
+                let a = self.read_mode(mode0)?;
+                let b = self.read_mode(mode1)?;
+                let c = self.read_mode_mut(mode2)?;
+
+                *c = match op {
+                    1 => add(a, b)?,
+                    2 => a.checked_mul(b).ok_or(Fault::Overflow(a, b))?,
+                    7 => if a < b { 1 } else { 0 },
+                    _ => if a == b { 1 } else { 0 },
+                };
+            }
+            3 => {
+                // resolve the address first so a bad operand doesn't consume input
+                let addr = self.write_addr(mode0)?;
+                if let Some(val) = self.input.pop_front() {
+                    *self.read_mut(addr) = val;
+                } else {
+                    self.pc = pc;
+                    return Ok(Some(IntCodeResult::Input));
                 }
-                _ => {
-                    let a = self.read_mode(mode0);
-                    let b = self.read_mode(mode1);
-                    let c = self.read_mode_mut(mode2);
-
-                    *c = match op {
-                        1 => a + b,
-                        2 => a * b,
-                        7 => if a < b { 1 } else { 0 },
-                        8 => if a == b { 1 } else { 0 },
-                        _ => panic!()
-                    };
+            }
+            4 => {
+                let val = self.read_mode(mode0)?;
+                return Ok(Some(IntCodeResult::Output(val)));
+            }
+            5 | 6 => {
+                let cond = self.read_mode(mode0)?;
+                let new_pc = self.read_mode(mode1)?;
+                if (cond != 0 && op == 5) || (cond == 0 && op == 6) {
+                    self.pc = to_addr(new_pc)?;
                 }
             }
+            9 => {
+                self.relative_base = add(self.relative_base, self.read_mode(mode0)?)?;
+            }
+            99 => {
+                self.is_halt = true;
+                return Ok(Some(IntCodeResult::Halt));
+            }
+            _ => return Err(Fault::UnknownOpcode),
         }
 
-        None
+        Ok(None)
     }
 
     fn decode(&mut self) -> (i64, i64, i64, i64) {
@@ -155,22 +376,28 @@ impl IntCode {
         (op, mode0, mode1, val)
     }
 
-    fn read_mode(&mut self, mode: i64) -> i64 {
+    fn read_mode(&mut self, mode: i64) -> Result<i64, Fault> {
         let val = self.read_pc();
         match mode {
-            0 => self.read(val as usize),
-            1 => val,
-            2 => self.read((self.relative_base + val) as usize),
-            _ => panic!()
+            0 => Ok(self.read(to_addr(val)?)),
+            1 => Ok(val),
+            2 => Ok(self.read(to_addr(add(self.relative_base, val)?)?)),
+            _ => Err(Fault::InvalidMode),
         }
     }
 
-    fn read_mode_mut(&mut self, mode: i64) -> &mut i64 {
+    fn read_mode_mut(&mut self, mode: i64) -> Result<&mut i64, Fault> {
+        let addr = self.write_addr(mode)?;
+        Ok(self.read_mut(addr))
+    }
+
+    fn write_addr(&mut self, mode: i64) -> Result<usize, Fault> {
         let val = self.read_pc();
         match mode {
-            0 => self.read_mut(val as usize),
-            2 => self.read_mut((self.relative_base + val) as usize),
-            _ => panic!()
+            0 => to_addr(val),
+            1 => Err(Fault::ImmediateWrite),
+            2 => to_addr(add(self.relative_base, val)?),
+            _ => Err(Fault::InvalidMode),
         }
     }
 
@@ -179,28 +406,19 @@ impl IntCode {
         self.read(self.pc - 1)
     }
 
-    fn read(&mut self, addr: usize) -> i64 {
-        self.ensure_memory(addr);
-        self.mem[addr]
+    fn read(&self, addr: usize) -> i64 {
+        self.mem.read(addr)
     }
 
     fn read_mut(&mut self, addr: usize) -> &mut i64 {
-        self.ensure_memory(addr);
-        &mut self.mem[addr]
+        self.mem.get_mut(addr)
     }
 
-    fn ensure_memory(&mut self, addr: usize) {
-        if addr >= self.mem.len() {
-            let size = usize::max(addr + 1, self.mem.len() * 2);
-            self.mem.resize(size, 0);
-        }
-    }
-
-    pub fn mem(&self) -> &[i64] {
+    pub fn mem(&self) -> &Memory {
         &self.mem
     }
 
-    pub fn mem_mut(&mut self) -> &mut [i64] {
+    pub fn mem_mut(&mut self) -> &mut Memory {
         &mut self.mem
     }
 
@@ -248,4 +466,170 @@ impl IntCodeAscii for IntCode {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{asm::assemble, IntCode, IntCodeError, IntCodeResult};
+
+    fn computer(source: &str) -> IntCode {
+        IntCode::new(assemble(source).unwrap())
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut computer = computer("
+                    add #2, #3, [x]
+                    mul [x], #-4, [x]
+                    out [x]
+                    hlt
+            x:      data 0");
+        assert_eq!(computer.run_to_halt().unwrap(), vec![-20]);
+    }
+
+    #[test]
+    fn compare_and_jump() {
+        // outputs 1 when the input is below 8, 2 when equal and 3 when above
+        let program = "
+                    in [x]
+                    lt [x], #8, [t]
+                    jt [t], #below
+                    eq [x], #8, [t]
+                    jt [t], #equal
+                    out #3
+                    hlt
+            below:  out #1
+                    hlt
+            equal:  out #2
+                    hlt
+            x:      data 0
+            t:      data 0";
+
+        for (input, expected) in [(7, 1), (8, 2), (9, 3)] {
+            assert_eq!(computer(program).run_input_to_halt(&[input]).unwrap(), vec![expected]);
+        }
+    }
+
+    #[test]
+    fn relative_base() {
+        let mut computer = computer("
+                    arb #stack
+                    add #5, #0, rb+1
+                    arb #1
+                    out rb
+                    out rb-1
+                    hlt
+            stack:  data 9");
+        assert_eq!(computer.run_to_halt().unwrap(), vec![5, 9]);
+    }
+
+    #[test]
+    fn waits_for_input() {
+        let mut computer = computer("
+            loop:   in [x]
+                    jf [x], #done
+                    out [x]
+                    jt #1, #loop
+            done:   hlt
+            x:      data 0");
+
+        assert!(matches!(computer.run(), IntCodeResult::Input));
+        assert!(matches!(computer.run_input(&[4]), IntCodeResult::Output(4)));
+        assert!(matches!(computer.run(), IntCodeResult::Input));
+        assert!(matches!(computer.run_input(&[0]), IntCodeResult::Halt));
+        assert!(computer.is_halt());
+    }
+
+    #[test]
+    fn grows_memory() {
+        let mut computer = computer("
+                    add #7, #0, [1000]
+                    out [1000]
+                    out [2000]
+                    hlt");
+        assert_eq!(computer.run_to_halt().unwrap(), vec![7, 0]);
+    }
+
+    #[test]
+    fn reset() {
+        let mut computer = computer("
+                    add [x], #1, [x]
+                    out [x]
+                    hlt
+            x:      data 0");
+        assert_eq!(computer.run_to_halt().unwrap(), vec![1]);
+        computer.reset();
+        assert_eq!(computer.run_to_halt().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |code: &[i64], input: &[i64]| {
+            let mut computer = IntCode::new(code.to_vec());
+            computer.add_input(input);
+            computer.try_run().unwrap_err()
+        };
+
+        assert_eq!(error(&[1101, 1, 1, 5, 42], &[]), IntCodeError::UnknownOpcode { pc: 4, opcode: 42 });
+        assert_eq!(error(&[109, 1, 304, 1], &[]), IntCodeError::InvalidMode { pc: 2, instruction: 304 });
+        assert_eq!(error(&[11101, 1, 1, 0], &[]), IntCodeError::ImmediateWrite { pc: 0, instruction: 11101 });
+        assert_eq!(error(&[109, -5, 204, 1], &[]), IntCodeError::NegativeAddress { pc: 2, address: -4 });
+        assert_eq!(error(&[1105, 1, -1], &[]), IntCodeError::NegativeAddress { pc: 0, address: -1 });
+        assert_eq!(error(&[3, -1], &[7]).to_string(), "negative address -1 at pc 0");
+        assert_eq!(error(&[1101, i64::MAX, 1, 0], &[]), IntCodeError::Overflow { pc: 0, a: i64::MAX, b: 1 });
+        assert_eq!(error(&[1102, i64::MIN, -1, 0], &[]), IntCodeError::Overflow { pc: 0, a: i64::MIN, b: -1 });
+        assert_eq!(error(&[109, i64::MAX, 109, 1], &[]), IntCodeError::Overflow { pc: 2, a: i64::MAX, b: 1 });
+    }
+
+    #[test]
+    fn error_leaves_state() {
+        let mut computer = IntCode::new(vec![109, -5, 203, 1, 99]);
+        computer.add_input(&[7]);
+        assert!(computer.try_step().unwrap().is_none());
+        assert_eq!(computer.try_step().unwrap_err(), IntCodeError::NegativeAddress { pc: 2, address: -4 });
+        assert_eq!(computer.pc(), computer.try_run().unwrap_err().pc());
+        assert_eq!(computer.input().len(), 1);
+
+        computer.mem_mut()[3] = 10;
+        assert!(matches!(computer.try_run(), Ok(IntCodeResult::Halt)));
+        assert_eq!(computer.mem()[5], 7);
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut computer = computer("
+            loop:   in [x]
+                    add [x], [total], [total]
+                    out [total]
+                    jt #1, #loop
+            x:      data 0
+            total:  data 0");
+        computer.run_input(&[5]);
+        let snapshot = computer.snapshot();
+        assert_eq!(computer.mem().shared_pages(&snapshot.mem), 1);
+
+        assert_eq!(computer.run_input(&[1]).unwrap(), 6);
+        assert_ne!(computer.state_hash(), snapshot.state_hash());
+
+        computer.restore(&snapshot);
+        assert_eq!(computer.state_hash(), snapshot.state_hash());
+        assert_eq!(computer.run_input(&[10]).unwrap(), 15);
+
+        // the same state reached along a different path hashes the same
+        let mut other = computer.clone();
+        other.reset();
+        other.run_input(&[2, 3, 10]);
+        other.run();
+        other.run();
+        assert_eq!(other.snapshot(), computer.snapshot());
+        assert_eq!(other.state_hash(), computer.state_hash());
+    }
+
+    #[test]
+    fn stable_hash() {
+        // pinned, so it has to come out the same on every platform
+        let mut computer = IntCode::from_string("3,5,99");
+        computer.add_input(&[-1, 1 << 40]);
+        assert_eq!(computer.state_hash(), 2571960265648704008);
+    }
+}
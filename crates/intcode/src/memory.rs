@@ -0,0 +1,234 @@
+use std::{hash::{Hash, Hasher}, ops::{Index, IndexMut}, rc::Rc};
+
+const PAGE_BITS: usize = 9;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+
+type Page = [i64; PAGE_SIZE];
+
+/// IntCode memory split into reference counted pages. Clones share every page until one side
+/// writes to it, so snapshotting a machine only copies the page table.
+///
+/// Equality and hashing ignore trailing zeros, a machine that touched but never wrote a far
+/// address compares equal to one that didn't.
+#[derive(Clone, Default)]
+pub struct Memory {
+    pages: Vec<Rc<Page>>,
+    len: usize,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_slice(values: &[i64]) -> Self {
+        let pages = values.chunks(PAGE_SIZE).map(|chunk| {
+            let mut page = [0; PAGE_SIZE];
+            page[..chunk.len()].copy_from_slice(chunk);
+            Rc::new(page)
+        }).collect();
+
+        Self { pages, len: values.len() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, addr: usize) -> Option<&i64> {
+        if addr < self.len {
+            Some(&self.pages[addr >> PAGE_BITS][addr & (PAGE_SIZE - 1)])
+        } else {
+            None
+        }
+    }
+
+    /// Reads any address, memory past the end is 0.
+    pub fn read(&self, addr: usize) -> i64 {
+        self.get(addr).copied().unwrap_or(0)
+    }
+
+    /// Mutable access to any address, growing memory to include it.
+    pub fn get_mut(&mut self, addr: usize) -> &mut i64 {
+        self.ensure(addr);
+        &mut Rc::make_mut(&mut self.pages[addr >> PAGE_BITS])[addr & (PAGE_SIZE - 1)]
+    }
+
+    pub fn write(&mut self, addr: usize, val: i64) {
+        *self.get_mut(addr) = val;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &i64> {
+        self.pages.iter().flat_map(|page| page.iter()).take(self.len)
+    }
+
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.iter().copied().collect()
+    }
+
+    /// Number of pages physically shared with `other`.
+    pub fn shared_pages(&self, other: &Memory) -> usize {
+        self.pages.iter().zip(other.pages.iter()).filter(|(a, b)| Rc::ptr_eq(a, b)).count()
+    }
+
+    fn ensure(&mut self, addr: usize) {
+        if addr >= self.len {
+            let pages = (addr >> PAGE_BITS) + 1;
+            if pages > self.pages.len() {
+                // untouched pages all share one zeroed page until they're written
+                self.pages.resize(pages, Rc::new([0; PAGE_SIZE]));
+            }
+            self.len = addr + 1;
+        }
+    }
+
+    fn trimmed_len(&self) -> usize {
+        let mut len = self.len;
+        while len > 0 && self[len - 1] == 0 {
+            len -= 1;
+        }
+        len
+    }
+}
+
+impl From<&[i64]> for Memory {
+    fn from(values: &[i64]) -> Self {
+        Self::from_slice(values)
+    }
+}
+
+impl Index<usize> for Memory {
+    type Output = i64;
+
+    fn index(&self, addr: usize) -> &i64 {
+        self.get(addr).unwrap_or_else(|| panic!("address {} out of bounds, memory size is {}", addr, self.len))
+    }
+}
+
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, addr: usize) -> &mut i64 {
+        assert!(addr < self.len, "address {} out of bounds, memory size is {}", addr, self.len);
+        self.get_mut(addr)
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        let len = self.trimmed_len();
+        len == other.trimmed_len() && self.iter().zip(other.iter()).take(len).all(|(a, b)| a == b)
+    }
+}
+
+impl Eq for Memory { }
+
+impl Hash for Memory {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let len = self.trimmed_len();
+        len.hash(state);
+        for val in self.iter().take(len) {
+            val.hash(state);
+        }
+    }
+}
+
+impl std::fmt::Debug for Memory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter().take(self.trimmed_len())).finish()
+    }
+}
+
+/// 64 bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so state hashes can be stored
+/// or compared across runs.
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write(&(i as u64).to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::{Hash, Hasher};
+
+    use super::{Memory, StableHasher, PAGE_SIZE};
+
+    fn hash(memory: &Memory) -> u64 {
+        let mut hasher = StableHasher::new();
+        memory.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let values: Vec<i64> = (0..3 * PAGE_SIZE as i64).collect();
+        let mut a = Memory::from_slice(&values);
+        let b = a.clone();
+        assert_eq!(a.shared_pages(&b), 3);
+
+        a[PAGE_SIZE + 1] = -1;
+        assert_eq!(a.shared_pages(&b), 2);
+        assert_eq!(a[PAGE_SIZE + 1], -1);
+        assert_eq!(b[PAGE_SIZE + 1], PAGE_SIZE as i64 + 1);
+        assert_eq!(b.to_vec(), values);
+    }
+
+    #[test]
+    fn grows() {
+        let mut memory = Memory::from_slice(&[1, 2, 3]);
+        assert_eq!(memory.read(10 * PAGE_SIZE), 0);
+        assert_eq!(memory.len(), 3);
+
+        memory.write(10 * PAGE_SIZE, 7);
+        assert_eq!(memory.len(), 10 * PAGE_SIZE + 1);
+        assert_eq!(memory[10 * PAGE_SIZE], 7);
+        assert_eq!(memory.iter().filter(|v| **v != 0).count(), 4);
+    }
+
+    #[test]
+    fn equality_ignores_trailing_zeros() {
+        let a = Memory::from_slice(&[1, 2, 3]);
+        let mut b = a.clone();
+        b.write(5000, 0);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+
+        b.write(5000, 1);
+        assert_ne!(a, b);
+        assert_ne!(hash(&a), hash(&b));
+
+        // fixed regardless of platform or process
+        assert_eq!(hash(&Memory::new()), 0xa8c7f832281a39c5);
+    }
+}
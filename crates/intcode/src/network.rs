@@ -0,0 +1,398 @@
+use std::{collections::VecDeque, fmt::{self, Display}};
+
+use crate::{IntCode, IntCodeError, IntCodeResult};
+
+/// Where a machine's output goes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// Reported to the caller as `Event::Output`.
+    Output,
+    /// Appended to the input of another machine.
+    Pipe(usize),
+    /// Appended to the input of every listed machine.
+    Broadcast(Vec<usize>),
+    /// Grouped into packets of an address followed by `payload` values. Packets addressed to a
+    /// machine are appended to its input, anything else goes to the NAT or is reported as
+    /// `Event::Packet`.
+    Bus { payload: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub from: usize,
+    pub to: i64,
+    pub payload: Vec<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Output { from: usize, value: i64 },
+    /// A bus packet that no machine or NAT accepted.
+    Packet(Packet),
+    /// The NAT woke the idle network up by sending this packet.
+    Nat(Packet),
+    /// Every machine is halted or waiting for input that hasn't arrived.
+    Idle,
+    Halted,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    Machine { node: usize, error: IntCodeError },
+    /// The stop condition ignored an `Idle` or `Halted` event without sending any input, so
+    /// nothing can ever change.
+    Deadlock,
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Machine { node, error } => write!(f, "machine {}: {}", node, error),
+            NetworkError::Deadlock => write!(f, "network deadlocked"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError { }
+
+/// Watches bus packets sent to its address and gets a chance to act whenever the network
+/// goes idle.
+pub trait Nat {
+    fn receive(&mut self, packet: Packet);
+    fn wake(&mut self) -> Option<Packet>;
+}
+
+/// Keeps the last packet it received and resends it to `to` whenever the network goes idle.
+pub struct RelayNat {
+    to: i64,
+    last: Option<Packet>,
+}
+
+impl RelayNat {
+    pub fn new(to: i64) -> Self {
+        Self { to, last: None }
+    }
+}
+
+impl Nat for RelayNat {
+    fn receive(&mut self, packet: Packet) {
+        self.last = Some(packet);
+    }
+
+    fn wake(&mut self) -> Option<Packet> {
+        self.last.as_ref().map(|packet| Packet { to: self.to, ..packet.clone() })
+    }
+}
+
+struct Node {
+    computer: IntCode,
+    channel: Channel,
+    packet: Vec<i64>,
+    last_output: Option<i64>,
+    polled: bool,
+    idle: bool,
+}
+
+/// Runs a set of machines round robin, each one until it halts or waits for input, routing
+/// their output through each machine's `Channel`.
+pub struct Network {
+    nodes: Vec<Node>,
+    current: usize,
+    events: VecDeque<Event>,
+    idle_input: Option<i64>,
+    nat: Option<(i64, Box<dyn Nat>)>,
+}
+
+impl Network {
+    pub fn new(computers: Vec<IntCode>) -> Self {
+        let nodes = computers.into_iter().map(|computer| Node {
+            computer,
+            channel: Channel::Output,
+            packet: Vec::new(),
+            last_output: None,
+            polled: false,
+            idle: false,
+        }).collect();
+
+        Self {
+            nodes,
+            current: 0,
+            events: VecDeque::new(),
+            idle_input: None,
+            nat: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, node: usize) -> &IntCode {
+        &self.nodes[node].computer
+    }
+
+    pub fn last_output(&self, node: usize) -> Option<i64> {
+        self.nodes[node].last_output
+    }
+
+    pub fn connect(&mut self, from: usize, channel: Channel) {
+        let targets = match &channel {
+            Channel::Pipe(to) => std::slice::from_ref(to),
+            Channel::Broadcast(to) => to.as_slice(),
+            _ => &[],
+        };
+        assert!(targets.iter().all(|to| *to < self.nodes.len()), "channel from {} to a missing machine", from);
+        self.nodes[from].channel = channel;
+    }
+
+    /// Value given to a machine waiting on an empty input, instead of blocking it.
+    pub fn set_idle_input(&mut self, val: i64) {
+        self.idle_input = Some(val);
+    }
+
+    pub fn set_nat<N: Nat + 'static>(&mut self, address: i64, nat: N) {
+        self.nat = Some((address, Box::new(nat)));
+    }
+
+    pub fn send(&mut self, to: usize, values: &[i64]) {
+        let node = &mut self.nodes[to];
+        node.computer.add_input(values);
+        node.polled = false;
+        node.idle = false;
+    }
+
+    /// True when every machine is halted or waiting for input nobody has sent.
+    pub fn is_idle(&self) -> bool {
+        self.nodes.iter().all(|node| node.idle || node.computer.is_halt())
+    }
+
+    pub fn next_event(&mut self) -> Result<Event, NetworkError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+
+            if self.current == self.nodes.len() {
+                self.current = 0;
+                if let Some(event) = self.end_round() {
+                    return Ok(event);
+                }
+            }
+
+            self.turn()?;
+        }
+    }
+
+    /// Runs until `stop` returns a value. `stop` sees every event and may send input to the
+    /// network, which is how a caller can act on `Idle` without a NAT.
+    pub fn run_until<T, F>(&mut self, mut stop: F) -> Result<T, NetworkError>
+    where
+        F: FnMut(&mut Self, Event) -> Option<T>,
+    {
+        loop {
+            let event = self.next_event()?;
+            let stalled = matches!(event, Event::Idle | Event::Halted);
+            if let Some(result) = stop(self, event) {
+                return Ok(result);
+            }
+            if stalled && self.is_idle() {
+                return Err(NetworkError::Deadlock);
+            }
+        }
+    }
+
+    fn end_round(&mut self) -> Option<Event> {
+        if self.nodes.iter().all(|node| node.computer.is_halt()) {
+            return Some(Event::Halted);
+        }
+        if !self.is_idle() {
+            return None;
+        }
+
+        let packet = self.nat.as_mut().and_then(|(_, nat)| nat.wake());
+        match packet {
+            Some(packet) => {
+                self.route_packet(packet.clone(), false);
+                Some(Event::Nat(packet))
+            }
+            None => Some(Event::Idle),
+        }
+    }
+
+    fn turn(&mut self) -> Result<(), NetworkError> {
+        let current = self.current;
+        let node = &mut self.nodes[current];
+        let result = node.computer.try_run().map_err(|error| NetworkError::Machine { node: current, error })?;
+
+        match result {
+            IntCodeResult::Output(value) => {
+                node.last_output = Some(value);
+                node.polled = false;
+                node.idle = false;
+                self.route(current, value);
+            }
+            IntCodeResult::Input => {
+                // a polling machine is only idle once it has seen the idle input and is back
+                // asking for more without having sent anything
+                if let Some(val) = self.idle_input {
+                    node.computer.push_input_back(val);
+                    node.idle = node.polled;
+                    node.polled = true;
+                } else {
+                    node.idle = true;
+                }
+                self.current += 1;
+            }
+            IntCodeResult::Halt => self.current += 1,
+        }
+        Ok(())
+    }
+
+    fn route(&mut self, from: usize, value: i64) {
+        match self.nodes[from].channel.clone() {
+            Channel::Output => self.events.push_back(Event::Output { from, value }),
+            Channel::Pipe(to) => self.send(to, &[value]),
+            Channel::Broadcast(to) => {
+                for to in to {
+                    self.send(to, &[value]);
+                }
+            }
+            Channel::Bus { payload } => {
+                let size = payload + 1;
+                let packet = &mut self.nodes[from].packet;
+                packet.push(value);
+                if packet.len() == size {
+                    let packet = std::mem::take(packet);
+                    self.route_packet(Packet { from, to: packet[0], payload: packet[1..].to_vec() }, true);
+                }
+            }
+        }
+    }
+
+    fn route_packet(&mut self, packet: Packet, to_nat: bool) {
+        if (0..self.nodes.len() as i64).contains(&packet.to) {
+            self.send(packet.to as usize, &packet.payload);
+            return;
+        }
+
+        match self.nat.as_mut() {
+            Some((address, nat)) if to_nat && *address == packet.to => nat.receive(packet),
+            _ => self.events.push_back(Event::Packet(packet)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Channel, Event, Nat, Network, NetworkError, Packet, RelayNat};
+    use crate::{asm::assemble, IntCode};
+
+    fn computer(source: &str) -> IntCode {
+        IntCode::new(assemble(source).unwrap())
+    }
+
+    // adds 1 to every input until it reads 0
+    const INCREMENT: &str = "
+        loop:   in [x]
+                jf [x], #done
+                add [x], #1, [x]
+                out [x]
+                jt #1, #loop
+        done:   hlt
+        x:      data 0";
+
+    #[test]
+    fn pipes() {
+        let mut network = Network::new(vec![computer(INCREMENT); 3]);
+        network.connect(0, Channel::Pipe(1));
+        network.connect(1, Channel::Broadcast(vec![2]));
+        network.send(0, &[1, 10]);
+
+        let mut outputs = Vec::new();
+        let result = network.run_until(|_, event| match event {
+            Event::Output { from, value } => { outputs.push((from, value)); None }
+            event => Some(event),
+        });
+        assert_eq!(result, Ok(Event::Idle));
+        assert_eq!(outputs, vec![(2, 4), (2, 13)]);
+        assert_eq!(network.last_output(1), Some(12));
+
+        for node in 0..network.len() {
+            network.send(node, &[0]);
+        }
+        assert_eq!(network.run_until(|_, event| Some(event)), Ok(Event::Halted));
+        assert_eq!(network.run_until(|_, _| None::<()>), Err(NetworkError::Deadlock));
+    }
+
+    #[test]
+    fn bus_and_nat() {
+        // node 0 sends its input + 1 to node 1, node 1 sends it on to 255, both poll with -1
+        let relay = "
+                    in [dest]
+            loop:   in [x]
+                    eq [x], #-1, [t]
+                    jt [t], #loop
+                    add [x], #1, [x]
+                    out [dest]
+                    out [x]
+                    jt #1, #loop
+            x:      data 0
+            t:      data 0
+            dest:   data 0";
+
+        let network = || {
+            let mut network = Network::new(vec![computer(relay); 2]);
+            network.connect(0, Channel::Bus { payload: 1 });
+            network.connect(1, Channel::Bus { payload: 1 });
+            network.send(0, &[1, 5]);
+            network.send(1, &[255]);
+            network.set_idle_input(-1);
+            network
+        };
+
+        let first = network().next_event().unwrap();
+        assert_eq!(first, Event::Packet(Packet { from: 1, to: 255, payload: vec![7] }));
+
+        let mut network = network();
+        network.set_nat(255, RelayNat::new(0));
+        let mut sent = Vec::new();
+        let result = network.run_until(|_, event| match event {
+            Event::Nat(packet) => {
+                sent.push(packet.payload[0]);
+                (sent.len() == 3).then_some(sent.clone())
+            }
+            _ => None,
+        });
+        assert_eq!(result, Ok(vec![7, 9, 11]));
+    }
+
+    #[test]
+    fn custom_nat() {
+        struct Counter(i64);
+        impl Nat for Counter {
+            fn receive(&mut self, _: Packet) { }
+            fn wake(&mut self) -> Option<Packet> {
+                self.0 += 1;
+                (self.0 <= 2).then_some(Packet { from: 0, to: 0, payload: vec![self.0] })
+            }
+        }
+
+        let mut network = Network::new(vec![computer(INCREMENT)]);
+        network.set_nat(99, Counter(0));
+        let events: Vec<Event> = std::iter::from_fn(|| match network.next_event().unwrap() {
+            Event::Idle => None,
+            event => Some(event),
+        }).collect();
+
+        assert_eq!(events, vec![
+            Event::Nat(Packet { from: 0, to: 0, payload: vec![1] }),
+            Event::Output { from: 0, value: 2 },
+            Event::Nat(Packet { from: 0, to: 0, payload: vec![2] }),
+            Event::Output { from: 0, value: 3 },
+        ]);
+    }
+}
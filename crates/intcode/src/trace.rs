@@ -0,0 +1,260 @@
+use std::{collections::{BTreeMap, HashMap, VecDeque}, fmt::{self, Display}};
+
+use crate::disasm::{Instruction, Op};
+
+/// One executed instruction. `values` are the operands as the instruction saw them, so
+/// position and relative operands are already read from memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub instruction: Instruction,
+    pub relative_base: i64,
+    pub values: Vec<i64>,
+    pub write: Option<(usize, i64)>,
+}
+
+impl TraceEvent {
+    pub fn pc(&self) -> usize {
+        self.instruction.addr
+    }
+
+    pub fn op(&self) -> Op {
+        self.instruction.op
+    }
+}
+
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = self.values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+        write!(f, "{:>6}: {:<32}({})", self.pc(), self.instruction.to_string(), values)?;
+        if let Some((addr, val)) = self.write {
+            write!(f, " -> [{}] = {}", addr, val)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hook called by `IntCode::step` after every executed instruction.
+pub trait Tracer {
+    fn on_step(&mut self, event: &TraceEvent);
+}
+
+impl<F: FnMut(&TraceEvent)> Tracer for F {
+    fn on_step(&mut self, event: &TraceEvent) {
+        self(event)
+    }
+}
+
+/// Records every event, or only the most recent `capacity` of them. A capacity of 0 records
+/// nothing.
+#[derive(Clone, Debug, Default)]
+pub struct TraceLog {
+    pub events: VecDeque<TraceEvent>,
+    capacity: Option<usize>,
+}
+
+impl TraceLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity: Some(capacity),
+        }
+    }
+}
+
+impl Tracer for TraceLog {
+    fn on_step(&mut self, event: &TraceEvent) {
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            if self.events.len() == capacity {
+                self.events.pop_front();
+            }
+        }
+        self.events.push_back(event.clone());
+    }
+}
+
+impl Display for TraceLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in self.events.iter() {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts executed instructions per address and per opcode. Displays as a summary with the
+/// hottest addresses first.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    by_addr: BTreeMap<usize, (Op, u64)>,
+    by_op: HashMap<Op, u64>,
+    total: u64,
+}
+
+impl Profiler {
+    const HOTTEST: usize = 20;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn count_at(&self, addr: usize) -> u64 {
+        self.by_addr.get(&addr).map_or(0, |(_, count)| *count)
+    }
+
+    pub fn count_of(&self, op: Op) -> u64 {
+        self.by_op.get(&op).copied().unwrap_or(0)
+    }
+
+    /// The `n` most executed addresses, most executed first and lowest address on ties.
+    pub fn hottest(&self, n: usize) -> Vec<(usize, Op, u64)> {
+        let mut hottest: Vec<(usize, Op, u64)> = self.by_addr.iter().map(|(addr, (op, count))| (*addr, *op, *count)).collect();
+        hottest.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        hottest.truncate(n);
+        hottest
+    }
+}
+
+impl Tracer for Profiler {
+    fn on_step(&mut self, event: &TraceEvent) {
+        self.by_addr.entry(event.pc()).or_insert((event.op(), 0)).1 += 1;
+        *self.by_op.entry(event.op()).or_insert(0) += 1;
+        self.total += 1;
+    }
+}
+
+impl Display for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} instructions executed", self.total)?;
+
+        let mut by_op: Vec<(Op, u64)> = self.by_op.iter().map(|(op, count)| (*op, *count)).collect();
+        by_op.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.code().cmp(&b.0.code())));
+        writeln!(f, "by opcode:")?;
+        for (op, count) in by_op {
+            writeln!(f, "  {:<4}{:>12}{:>8.2}%", op, count, percent(count, self.total))?;
+        }
+
+        writeln!(f, "hottest addresses:")?;
+        for (addr, op, count) in self.hottest(Self::HOTTEST) {
+            writeln!(f, "  {:>6} {:<4}{:>12}{:>8.2}%", addr, op, count, percent(count, self.total))?;
+        }
+        Ok(())
+    }
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Profiler, TraceEvent, TraceLog};
+    use crate::{asm::assemble, disasm::Op, IntCode};
+
+    const COUNTDOWN: &str = "
+                in [n]
+        loop:   out [n]
+                add [n], #-1, [n]
+                jt [n], #loop
+                hlt
+        n:      data 0";
+
+    #[test]
+    fn trace_log() {
+        let log = Rc::new(RefCell::new(TraceLog::new()));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        computer.set_tracer(log.clone());
+        assert_eq!(computer.run_input_to_halt(&[2]).unwrap(), vec![2, 1]);
+
+        let log = log.borrow();
+        let listing = log.to_string();
+        let expected = "
+     0: in [12]                         () -> [12] = 2
+     2: out [12]                        (2)
+     4: add [12], #-1, [12]             (2, -1) -> [12] = 1
+     8: jt [12], #2                     (1, 2)
+     2: out [12]                        (1)
+     4: add [12], #-1, [12]             (1, -1) -> [12] = 0
+     8: jt [12], #2                     (0, 2)
+    11: hlt                             ()
+";
+        assert_eq!(listing, expected[1..]);
+    }
+
+    #[test]
+    fn bounded_log() {
+        let log = Rc::new(RefCell::new(TraceLog::with_capacity(2)));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        computer.set_tracer(log.clone());
+        computer.run_input_to_halt(&[5]).unwrap();
+
+        let log = log.borrow();
+        assert_eq!(log.events.iter().map(|e| e.op()).collect::<Vec<_>>(), vec![Op::Jt, Op::Hlt]);
+    }
+
+    #[test]
+    fn bounded_log_wraps() {
+        let log = Rc::new(RefCell::new(TraceLog::with_capacity(3)));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        computer.set_tracer(log.clone());
+        computer.run_input_to_halt(&[2]).unwrap();
+
+        // the last 3 of the 8 steps, oldest first
+        let log = log.borrow();
+        assert_eq!(log.events.iter().map(|e| (e.pc(), e.op())).collect::<Vec<_>>(), vec![(4, Op::Add), (8, Op::Jt), (11, Op::Hlt)]);
+        assert_eq!(log.events[0].write, Some((12, 0)));
+
+        let empty = Rc::new(RefCell::new(TraceLog::with_capacity(0)));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        computer.set_tracer(empty.clone());
+        computer.run_input_to_halt(&[2]).unwrap();
+        assert!(empty.borrow().events.is_empty());
+    }
+
+    #[test]
+    fn profiler() {
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        computer.set_tracer(profiler.clone());
+        computer.run_input_to_halt(&[10]).unwrap();
+
+        let profiler = profiler.borrow();
+        assert_eq!(profiler.total(), 32);
+        assert_eq!(profiler.count_of(Op::Out), 10);
+        assert_eq!(profiler.count_of(Op::In), 1);
+        assert_eq!(profiler.count_at(4), 10);
+        assert_eq!(profiler.hottest(2), vec![(2, Op::Out, 10), (4, Op::Add, 10)]);
+    }
+
+    #[test]
+    fn closure() {
+        let writes = Rc::new(RefCell::new(Vec::new()));
+        let mut computer = IntCode::new(assemble(COUNTDOWN).unwrap());
+        let recorded = writes.clone();
+        computer.set_tracer(Rc::new(RefCell::new(move |event: &TraceEvent| recorded.borrow_mut().extend(event.write))));
+        assert!(matches!(computer.run(), crate::IntCodeResult::Input));
+        assert!(writes.borrow().is_empty());
+
+        computer.run_input_to_halt(&[2]).unwrap();
+        computer.clear_tracer();
+        computer.reset();
+        computer.run_input_to_halt(&[2]).unwrap();
+        assert_eq!(*writes.borrow(), vec![(12, 2), (12, 1), (12, 0)]);
+    }
+}